@@ -0,0 +1,251 @@
+use crate::vec3::{Color, Quat, Vec3};
+use std::ops::{Add, Mul};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Interpolation {
+    // Holds each keyframe's value until the next keyframe.
+    Step,
+
+    // Moves between keyframes at a constant speed. Rotations are slerped.
+    Linear,
+
+    // Passes smoothly through the keyframes, along a Catmull-Rom spline. Rotations are slerped, as for `Linear`.
+    Cubic,
+}
+
+/// A value that can be keyframed.
+pub trait Interpolate: Copy {
+    fn lerp(&self, rhs: &Self, t: f64) -> Self;
+
+    /// Interpolates between `p[1]` and `p[2]`, given the keyframes either side of them. At the ends of a track the
+    /// missing keyframes are repeated.
+    fn cubic(p: [&Self; 4], times: [f64; 4], t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn lerp(&self, rhs: &f64, t: f64) -> f64 {
+        self * (1. - t) + rhs * t
+    }
+
+    fn cubic(p: [&f64; 4], times: [f64; 4], t: f64) -> f64 {
+        catmull_rom(p.map(|p| *p), times, t)
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn lerp(&self, rhs: &Vec3, t: f64) -> Vec3 {
+        self * (1. - t) + rhs * t
+    }
+
+    fn cubic(p: [&Vec3; 4], times: [f64; 4], t: f64) -> Vec3 {
+        catmull_rom(p.map(|p| *p), times, t)
+    }
+}
+
+impl Interpolate for Quat {
+    fn lerp(&self, rhs: &Quat, t: f64) -> Quat {
+        self.slerp(rhs, t)
+    }
+
+    fn cubic(p: [&Quat; 4], _times: [f64; 4], t: f64) -> Quat {
+        p[1].slerp(p[2], t)
+    }
+}
+
+/// A cubic Hermite spline from `p[1]` to `p[2]` whose tangents are the slopes between the keyframes either side, scaled
+/// for keyframes that aren't evenly spaced in time.
+fn catmull_rom<T>(p: [T; 4], times: [f64; 4], t: f64) -> T
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    let tangent = |a: T, b: T, dt: f64| {
+        if dt > 0. {
+            (b + a * -1.) * ((times[2] - times[1]) / dt)
+        } else {
+            b + a * -1.
+        }
+    };
+    let m1 = tangent(p[0], p[2], times[2] - times[0]);
+    let m2 = tangent(p[1], p[3], times[3] - times[1]);
+
+    let (t2, t3) = (t * t, t * t * t);
+    p[1] * (2. * t3 - 3. * t2 + 1.)
+        + m1 * (t3 - 2. * t2 + t)
+        + p[2] * (-2. * t3 + 3. * t2)
+        + m2 * (t3 - t2)
+}
+
+/// The values a property takes over time.
+pub struct Track<T: Interpolate> {
+    pub interpolation: Interpolation,
+    pub keyframes: Vec<(f64, T)>,
+}
+
+impl<T: Interpolate> Track<T> {
+    /// # Arguments
+    ///
+    /// * `keyframes`: the times, in seconds, and values of the keyframes, in any order. There must be at least one.
+    ///
+    pub fn new(interpolation: Interpolation, mut keyframes: Vec<(f64, T)>) -> Track<T> {
+        assert!(!keyframes.is_empty(), "A track needs at least one keyframe");
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Track {
+            interpolation,
+            keyframes,
+        }
+    }
+
+    /// The value at `time`. Before the first keyframe and after the last the value is held.
+    pub fn sample(&self, time: f64) -> T {
+        let keyframes = &self.keyframes;
+        let next = keyframes.partition_point(|&(t, _)| t <= time);
+        if next == 0 {
+            return keyframes[0].1;
+        } else if next == keyframes.len() {
+            return keyframes[next - 1].1;
+        }
+
+        let (t1, p1) = &keyframes[next - 1];
+        let (t2, p2) = &keyframes[next];
+        let t = (time - t1) / (t2 - t1);
+        match self.interpolation {
+            Interpolation::Step => *p1,
+            Interpolation::Linear => p1.lerp(p2, t),
+            Interpolation::Cubic => {
+                let (t0, p0) = &keyframes[next.saturating_sub(2)];
+                let (t3, p3) = &keyframes[(next + 1).min(keyframes.len() - 1)];
+                T::cubic([p0, p1, p2, p3], [*t0, *t1, *t2, *t3], t)
+            }
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.keyframes[self.keyframes.len() - 1].0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Target {
+    Camera,
+
+    // A node in the scene, found by following the indexes of children from the scene's roots. A scene without a
+    // hierarchy has a path of one index.
+    Node(Vec<usize>),
+
+    // A light, by its index in the scene.
+    Light(usize),
+}
+
+pub enum Channel {
+    // The position of a camera, node or point light.
+    Translation(Track<Vec3>),
+
+    // The orientation of a camera or node.
+    Rotation(Track<Quat>),
+
+    // The scale of a node.
+    Scale(Track<Vec3>),
+
+    // The direction of a directional light.
+    Direction(Track<Vec3>),
+
+    // The intensity of a light, in each of red, green and blue.
+    Intensity(Track<Color>),
+}
+
+impl Channel {
+    pub fn duration(&self) -> f64 {
+        match self {
+            Channel::Translation(track)
+            | Channel::Scale(track)
+            | Channel::Direction(track)
+            | Channel::Intensity(track) => track.duration(),
+            Channel::Rotation(track) => track.duration(),
+        }
+    }
+}
+
+/// Keyframed changes to a scene. Channels that don't apply to their target, like the scale of a light, are ignored.
+pub struct Timeline {
+    pub channels: Vec<(Target, Channel)>,
+}
+
+impl Timeline {
+    pub fn new(channels: Vec<(Target, Channel)>) -> Timeline {
+        Timeline { channels }
+    }
+
+    /// The time of the last keyframe, after which nothing changes.
+    pub fn duration(&self) -> f64 {
+        self.channels
+            .iter()
+            .map(|(_target, channel)| channel.duration())
+            .fold(0., f64::max)
+    }
+
+    /// The time of each frame of the whole timeline, the first at 0 and the last at or just after `duration`.
+    pub fn frame_times(&self, frames_per_second: f64) -> Vec<f64> {
+        let frames = (self.duration() * frames_per_second).ceil() as usize + 1;
+        (0..frames)
+            .map(|frame| frame as f64 / frames_per_second)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::animation::{Interpolation, Track};
+    use crate::vec3::{Quat, Vec3};
+
+    #[test]
+    fn test_track() {
+        let keyframes = vec![(2., 10.), (0., 0.), (3., 0.)];
+        let step = Track::new(Interpolation::Step, keyframes.clone());
+        let linear = Track::new(Interpolation::Linear, keyframes.clone());
+        let cubic = Track::new(Interpolation::Cubic, keyframes);
+
+        for track in [&step, &linear, &cubic] {
+            assert_eq!(track.sample(-1.), 0.);
+            assert_eq!(track.sample(0.), 0.);
+            assert_eq!(track.sample(2.), 10.);
+            assert_eq!(track.sample(4.), 0.);
+            assert_eq!(track.duration(), 3.);
+        }
+        assert_eq!(step.sample(1.), 0.);
+        assert_eq!(linear.sample(1.), 5.);
+
+        // The spline levels out at the peak rather than turning sharply.
+        assert_eq!(cubic.sample(1.), 6.25);
+        assert!(cubic.sample(1.9) > linear.sample(1.9));
+        assert!(cubic.sample(2.1) > linear.sample(2.1));
+    }
+
+    #[test]
+    fn test_cubic_is_linear_for_evenly_spaced_points_on_a_line() {
+        let track = Track::new(
+            Interpolation::Cubic,
+            vec![
+                (0., Vec3::new(0., 0., 0.)),
+                (1., Vec3::new(1., 2., 3.)),
+                (2., Vec3::new(2., 4., 6.)),
+                (3., Vec3::new(3., 6., 9.)),
+            ],
+        );
+        let p = track.sample(1.25);
+        assert!((&p - &Vec3::new(1.25, 2.5, 3.75)).len() < 1e-9);
+    }
+
+    #[test]
+    fn test_rotation_track() {
+        let axis = Vec3::new(0., 1., 0.);
+        let track = Track::new(
+            Interpolation::Linear,
+            vec![
+                (0., Quat::from_axis_angle(&axis, 0.)),
+                (1., Quat::from_axis_angle(&axis, 90.)),
+            ],
+        );
+        let (_axis, angle) = track.sample(0.5).to_axis_angle();
+        assert!((angle - 45.).abs() < 1e-9);
+    }
+}
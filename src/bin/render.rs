@@ -0,0 +1,182 @@
+use anyhow::{anyhow, bail, Context, Result};
+use cg_from_scratch_template::color::ToneMapping;
+use cg_from_scratch_template::renderer::Backend;
+use cg_from_scratch_template::scene::examples::Example;
+use cg_from_scratch_template::scene::file;
+use cg_from_scratch_template::viewer::Viewer;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+use std::env;
+use std::path::Path;
+
+const USAGE: &str = "\
+Renders a scene to an image file.
+
+Usage: render [OPTIONS] <OUTPUT>
+
+The format of OUTPUT is chosen by its extension: .png, .ppm or .exr. EXR files keep the linear colors as they were
+rendered, so they aren't tone mapped.
+
+Options:
+  --renderer <rasterizer|raytracer>                Which renderer to use [default: rasterizer]
+  --scene <crates|spheres|materials|cobblestones>  Which of the built-in scenes to render [default: crates for the
+                                                   rasterizer, spheres for the raytracer]
+  --scene-file <PATH>                              A JSON scene file to render instead, such as
+                                                   scenes/spheres.json
+  --width <PIXELS>                                 The width of the image [default: 600]
+  --height <PIXELS>                                The height of the image [default: 600]
+  --time <SECONDS>                                 How far into the scene's animation to render it [default: 0]
+  --tone-mapping <aces|reinhard|exposure|clamp>    How colors too bright to show are brought in range
+                                                   [default: aces]
+  --exposure <FACTOR>                              What the colors are multiplied by before they're tone mapped
+                                                   [default: 1]
+  --help                                           Print this message
+
+What it took to render the image is printed to stderr.";
+
+/// Where the scene to render comes from.
+enum SceneSource {
+    Example(Example),
+    File(String),
+}
+
+struct Options {
+    renderer: Backend,
+    scene: SceneSource,
+    width: usize,
+    height: usize,
+    time: f64,
+    tone_mapping: ToneMapping,
+    exposure: f64,
+    output: String,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options> {
+    let mut renderer = Backend::Rasterizer;
+    let mut scene = None;
+    let (mut width, mut height, mut time) = (600, 600, 0.);
+    let (mut tone_mapping, mut exposure) = (ToneMapping::default(), 1.);
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+        match arg.as_str() {
+            "--renderer" => {
+                renderer = match value()?.as_str() {
+                    "rasterizer" => Backend::Rasterizer,
+                    "raytracer" => Backend::Raytracer,
+                    other => bail!("Unknown renderer {}", other),
+                }
+            }
+            "--scene" | "--scene-file" if scene.is_some() => {
+                bail!("Only one of --scene and --scene-file can be given")
+            }
+            "--scene" => {
+                scene = Some(SceneSource::Example(match value()?.as_str() {
+                    "crates" => Example::Crates,
+                    "spheres" => Example::Spheres,
+                    "materials" => Example::Materials,
+                    "cobblestones" => Example::Cobblestones,
+                    other => bail!("Unknown scene {}", other),
+                }))
+            }
+            "--scene-file" => scene = Some(SceneSource::File(value()?)),
+            "--width" => width = value()?.parse().context("--width must be a whole number")?,
+            "--height" => {
+                height = value()?
+                    .parse()
+                    .context("--height must be a whole number")?
+            }
+            "--time" => time = value()?.parse().context("--time must be a number")?,
+            "--tone-mapping" => {
+                tone_mapping = match value()?.as_str() {
+                    "aces" => ToneMapping::Aces,
+                    "reinhard" => ToneMapping::Reinhard,
+                    "exposure" => ToneMapping::Exposure,
+                    "clamp" => ToneMapping::Clamp,
+                    other => bail!("Unknown tone mapping {}", other),
+                }
+            }
+            "--exposure" => exposure = value()?.parse().context("--exposure must be a number")?,
+            "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if arg.starts_with("--") => bail!("Unknown option {}", arg),
+            _ if output.is_none() => output = Some(arg),
+            _ => bail!("Only one output file can be given"),
+        }
+    }
+
+    if width == 0 || height == 0 {
+        bail!("The image must be at least one pixel wide and high");
+    }
+    let scene = scene.unwrap_or(SceneSource::Example(match renderer {
+        Backend::Rasterizer => Example::Crates,
+        Backend::Raytracer => Example::Spheres,
+    }));
+    Ok(Options {
+        renderer,
+        scene,
+        width,
+        height,
+        time,
+        tone_mapping,
+        exposure,
+        output: output.ok_or_else(|| anyhow!("No output file given"))?,
+    })
+}
+
+fn save(viewer: &Viewer, width: usize, height: usize, output: &str) -> Result<()> {
+    let wrong_size = || anyhow!("The renderer returned the wrong number of pixels");
+    let image = RgbaImage::from_raw(width as u32, height as u32, viewer.pixels().to_vec())
+        .ok_or_else(wrong_size)?;
+    let image = DynamicImage::ImageRgba8(image);
+
+    let extension = Path::new(output)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("png") => image.save(output),
+        // PPM has no alpha channel, so transparent pixels are black.
+        Some("ppm") => image.to_rgb8().save(output),
+        Some("exr") => {
+            let colors =
+                Rgba32FImage::from_raw(width as u32, height as u32, viewer.colors().to_vec())
+                    .ok_or_else(wrong_size)?;
+            DynamicImage::ImageRgba32F(colors).save(output)
+        }
+        _ => bail!("{} isn't a .png, .ppm or .exr file", output),
+    }
+    .with_context(|| format!("Couldn't write {}", output))
+}
+
+fn main() -> Result<()> {
+    let options = parse_options(env::args().skip(1)).map_err(|e| anyhow!("{}\n\n{}", e, USAGE))?;
+    let Options {
+        renderer,
+        scene,
+        width,
+        height,
+        time,
+        tone_mapping,
+        exposure,
+        output,
+    } = options;
+
+    let mut viewer = match scene {
+        SceneSource::Example(example) => Viewer::new(renderer, example, height, width),
+        SceneSource::File(path) => {
+            Viewer::from_scene(renderer, file::load(Path::new(&path))?, height, width)
+        }
+    };
+    viewer.set_time(time);
+    viewer.set_tone_mapping(tone_mapping);
+    viewer.set_exposure(exposure);
+    let statistics = viewer.render();
+    eprintln!(
+        "{} objects, {} triangles, {} rays",
+        statistics.objects, statistics.triangles, statistics.rays
+    );
+    save(&viewer, width, height, &output)
+}
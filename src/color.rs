@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+/// Converts a linear color channel, from 0 to 1, to the sRGB encoding that displays and image files expect.
+pub fn encode_srgb(linear: f64) -> f64 {
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Converts an sRGB encoded color channel, from 0 to 1, to linear light, where it can be lit and blended.
+pub fn decode_srgb(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// How colors brighter than a display can show are brought within its range, after being scaled by the exposure.
+#[wasm_bindgen]
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum ToneMapping {
+    // Colors brighter than white are clipped to it, so bright highlights turn flat white.
+    Clamp,
+
+    // x / (1 + x), which never quite reaches white and keeps the darker colors as they are.
+    Reinhard,
+
+    // Narkowicz's fit of the filmic curve from the Academy Color Encoding System, which adds contrast to the mid tones
+    // and rolls highlights off smoothly to white.
+    #[default]
+    Aces,
+
+    // 1 - e^-x, the response of photographic film to the light it's exposed to.
+    Exposure,
+}
+
+impl ToneMapping {
+    /// Maps a linear color channel from 0 to any brightness into 0 to 1.
+    pub fn map(&self, linear: f64) -> f64 {
+        let x = linear.max(0.);
+        match self {
+            ToneMapping::Clamp => x.min(1.),
+            ToneMapping::Reinhard => x / (1. + x),
+            ToneMapping::Aces => {
+                ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0., 1.)
+            }
+            ToneMapping::Exposure => 1. - (-x).exp(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::{decode_srgb, encode_srgb, ToneMapping};
+
+    #[test]
+    fn test_srgb_round_trip() {
+        for byte in 0..=255 {
+            let encoded = byte as f64 / 255.;
+            assert!((encode_srgb(decode_srgb(encoded)) - encoded).abs() < 1e-9);
+        }
+        // Middle gray on screen is about a fifth of the light of white.
+        assert!((decode_srgb(0.5) - 0.214).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_tone_mapping() {
+        for tone_mapping in [
+            ToneMapping::Clamp,
+            ToneMapping::Reinhard,
+            ToneMapping::Aces,
+            ToneMapping::Exposure,
+        ] {
+            assert_eq!(tone_mapping.map(0.), 0.);
+            assert_eq!(tone_mapping.map(-1.), 0.);
+
+            // Brighter colors stay brighter, up to white.
+            let mapped = [0.1, 0.5, 1., 2., 10.].map(|x| tone_mapping.map(x));
+            assert!(mapped.windows(2).all(|pair| pair[0] <= pair[1]));
+            assert!(mapped[4] <= 1.);
+        }
+
+        // Only clamping clips the highlights.
+        assert_eq!(ToneMapping::Clamp.map(2.), ToneMapping::Clamp.map(10.));
+        assert!(ToneMapping::Reinhard.map(2.) < ToneMapping::Reinhard.map(10.));
+        assert!(ToneMapping::Exposure.map(2.) < ToneMapping::Exposure.map(10.));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::vec3::{Mat3, Vec3};
+
+const MIN_DISTANCE: f64 = 0.1;
+const MAX_PITCH: f64 = 89.;
+
+/// Orbit and fly-through controls for a camera that doesn't roll. The camera always faces a target point some
+/// distance in front of it: orbiting turns the camera around the target, looking turns it in place, dragging the target
+/// along with it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CameraControls {
+    pub target: Vec3,
+    pub distance: f64,
+    pub yaw: f64,
+    pub pitch: f64,
+}
+
+impl CameraControls {
+    /// # Arguments
+    ///
+    /// * `position`: where the camera is.
+    /// * `yaw`, `pitch`: the camera's orientation, in degrees, as for `Mat3::new_euler_rotation_matrix`.
+    /// * `distance`: how far in front of the camera the target is.
+    ///
+    pub fn new(position: Vec3, yaw: f64, pitch: f64, distance: f64) -> CameraControls {
+        let mut controls = CameraControls {
+            target: position,
+            distance,
+            yaw,
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+        };
+        controls.target = position + controls.forward() * distance;
+        controls
+    }
+
+    /// Controls for a camera with any orientation, which lose its roll.
+    pub fn from_orientation(position: Vec3, orientation: &Mat3, distance: f64) -> CameraControls {
+        let forward = orientation * Vec3::new(0., 0., 1.);
+        let forward = forward / forward.len();
+        let yaw = (-forward[0]).atan2(forward[2]).to_degrees();
+        let pitch = forward[1].clamp(-1., 1.).asin().to_degrees();
+        CameraControls::new(position, yaw, pitch, distance)
+    }
+
+    pub fn orientation(&self) -> Mat3 {
+        Mat3::new_euler_rotation_matrix(self.yaw, self.pitch, 0.)
+    }
+
+    pub fn position(&self) -> Vec3 {
+        &self.target - &(self.forward() * self.distance)
+    }
+
+    fn forward(&self) -> Vec3 {
+        &self.orientation() * Vec3::new(0., 0., 1.)
+    }
+
+    /// Turns the camera around the target, in degrees. A positive yaw turns the camera to its left, and so moves it to
+    /// its right; a positive pitch turns it up, moving it down.
+    pub fn orbit(&mut self, yaw: f64, pitch: f64) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Turns the camera where it stands, in degrees.
+    pub fn look(&mut self, yaw: f64, pitch: f64) {
+        let position = self.position();
+        self.orbit(yaw, pitch);
+        self.target = position + self.forward() * self.distance;
+    }
+
+    /// Moves the camera towards the target, closing the distance by `factor`; a factor below 1 moves it away.
+    pub fn zoom(&mut self, factor: f64) {
+        self.distance = (self.distance / factor).max(MIN_DISTANCE);
+    }
+
+    /// Moves the camera and its target together, along the camera's own axes.
+    pub fn fly(&mut self, right: f64, up: f64, forward: f64) {
+        self.target = self.target + &self.orientation() * Vec3::new(right, up, forward);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::controls::CameraControls;
+    use crate::vec3::{Mat3, Vec3};
+
+    fn assert_vec3_eq(a: &Vec3, b: &Vec3) {
+        assert!((a - b).len() < 1e-9, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_orbit() {
+        let mut controls = CameraControls::new(Vec3::new(0., 0., -5.), 0., 0., 5.);
+        assert_vec3_eq(&controls.target, &Vec3::new(0., 0., 0.));
+
+        controls.orbit(90., 0.);
+        assert_vec3_eq(&controls.position(), &Vec3::new(5., 0., 0.));
+        assert_vec3_eq(&controls.target, &Vec3::new(0., 0., 0.));
+
+        controls.zoom(2.);
+        assert_vec3_eq(&controls.position(), &Vec3::new(2.5, 0., 0.));
+
+        controls.orbit(0., 200.);
+        assert_eq!(controls.pitch, 89.);
+    }
+
+    #[test]
+    fn test_look_and_fly() {
+        let mut controls = CameraControls::new(Vec3::new(0., 0., -5.), 0., 0., 5.);
+        controls.look(90., 0.);
+        assert_vec3_eq(&controls.position(), &Vec3::new(0., 0., -5.));
+        assert_vec3_eq(&controls.target, &Vec3::new(-5., 0., -5.));
+
+        // Facing -x, the camera's right is +z.
+        controls.fly(1., 2., 3.);
+        assert_vec3_eq(&controls.position(), &Vec3::new(-3., 2., -4.));
+    }
+
+    #[test]
+    fn test_from_orientation() {
+        // Controls for a camera looking at a point keep it where it is, looking at that point.
+        let position = Vec3::new(3., 0., 1.);
+        let target = Vec3::new(0., 2., 4.);
+        let orientation = Mat3::new_look_at(&position, &target, &Vec3::new(0., 1., 0.));
+        let distance = (&target - &position).len();
+        let controls = CameraControls::from_orientation(position, &orientation, distance);
+        assert_vec3_eq(&controls.position(), &position);
+        assert_vec3_eq(&controls.target, &target);
+    }
+}
@@ -0,0 +1,115 @@
+//! Compares renders against reference images checked in under `tests/golden`.
+//!
+//! Run the tests with `UPDATE_GOLDEN=1` to write the references from the current renders, after checking that a change
+//! in the output is intended. When a render doesn't match, it's written to `target/golden` along with an image that
+//! shows where it differs.
+
+use image::{Rgba, RgbaImage};
+use std::env;
+use std::path::PathBuf;
+
+// A pixel differs if any of its channels is further than this from the reference. Small differences are expected from
+// floating point changes that move an edge by a fraction of a pixel, or round a color the other way.
+const CHANNEL_TOLERANCE: u8 = 8;
+
+// The fraction of the pixels that can differ before a render no longer matches.
+const MAX_DIFFERING_PIXELS: f64 = 0.002;
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name))
+}
+
+fn output_path(name: &str, suffix: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(format!("{}-{}.png", name, suffix))
+}
+
+/// The reference dimmed to gray, with the pixels that differ in red.
+fn diff_image(actual: &RgbaImage, expected: &RgbaImage) -> (RgbaImage, usize) {
+    let mut differing = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (a, e) = (actual.get_pixel(x, y), expected.get_pixel(x, y));
+        let distance = (0..4).map(|c| a[c].abs_diff(e[c])).max().unwrap();
+        if distance > CHANNEL_TOLERANCE {
+            differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let gray = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 3 * e[3] as u32 / 255;
+            let gray = (gray / 4 + 191) as u8;
+            Rgba([gray, gray, gray, 255])
+        }
+    });
+    (diff, differing)
+}
+
+/// Panics unless the RGBA `pixels` match the reference image called `name`.
+pub fn assert_matches_golden(name: &str, pixels: &[u8], width: usize, height: usize) {
+    let actual = RgbaImage::from_raw(width as u32, height as u32, pixels.to_vec()).unwrap();
+    let path = reference_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        actual.save(&path).unwrap();
+        return;
+    }
+
+    let expected = match image::open(&path) {
+        Ok(expected) => expected.to_rgba8(),
+        Err(e) => {
+            let actual_path = output_path(name, "actual");
+            actual.save(&actual_path).unwrap();
+            panic!(
+                "Couldn't read the reference {}: {}. The render is at {}; run with UPDATE_GOLDEN=1 to make it the \
+                 reference.",
+                path.display(),
+                e,
+                actual_path.display()
+            );
+        }
+    };
+    if expected.dimensions() != actual.dimensions() {
+        panic!(
+            "{} is {:?} but the render is {:?}",
+            path.display(),
+            expected.dimensions(),
+            actual.dimensions()
+        );
+    }
+
+    let (diff, differing) = diff_image(&actual, &expected);
+    let fraction = differing as f64 / (width * height) as f64;
+    if fraction > MAX_DIFFERING_PIXELS {
+        let (actual_path, diff_path) = (output_path(name, "actual"), output_path(name, "diff"));
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{} pixels ({:.2}%) differ from {}. The render is at {} and the differences are marked in {}.",
+            differing,
+            fraction * 100.,
+            path.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::golden::diff_image;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_diff_image() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 1, Rgba([105, 100, 100, 255]));
+        actual.put_pixel(2, 2, Rgba([100, 100, 100, 0]));
+
+        let (diff, differing) = diff_image(&actual, &expected);
+        assert_eq!(differing, 1);
+        assert_eq!(diff.get_pixel(2, 2), &Rgba([255, 0, 0, 255]));
+        assert_ne!(diff.get_pixel(1, 1), &Rgba([255, 0, 0, 255]));
+    }
+}
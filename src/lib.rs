@@ -1,8 +1,16 @@
 extern crate web_sys;
+mod animation;
+pub mod color;
+mod controls;
+#[cfg(test)]
+mod golden;
 pub mod rasterizer;
 pub mod raytracer;
+pub mod renderer;
+pub mod scene;
 mod utils;
 mod vec3;
+pub mod viewer;
 
 use wasm_bindgen::prelude::*;
 
@@ -23,3 +31,9 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 extern "C" {
     fn alert(s: &str);
 }
+
+/// The module's memory, so JS can view the renderers' pixels in place.
+#[wasm_bindgen]
+pub fn memory() -> JsValue {
+    wasm_bindgen::memory()
+}
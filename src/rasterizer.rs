@@ -1,14 +1,12 @@
 mod camera;
 mod canvas;
 mod instance;
-mod light;
 pub mod main;
 mod model;
 mod plane;
 mod point;
-mod scene;
+mod rasterization;
 mod shading;
 mod surface;
-mod texture;
 mod triangle;
 mod util;
@@ -1,22 +1,230 @@
 use crate::rasterizer::plane::Plane;
+use crate::rasterizer::util::PROJECTION_PLANE_Z;
+use crate::scene;
 use crate::vec3::{Mat3, Mat4, Vec3};
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Projection {
+    // Rays converge on the camera, so distant objects look smaller.
+    Perspective,
+
+    // Rays are parallel to the camera's z axis, so objects look the same size however far away they are.
+    Orthographic,
+}
+
+impl Projection {
+    /// A value that varies linearly across the screen, from which the depth of any point on a triangle can be
+    /// interpolated.
+    pub fn linear_depth(&self, z: f64) -> f64 {
+        match self {
+            Projection::Perspective => 1. / z,
+            Projection::Orthographic => z,
+        }
+    }
+
+    /// Converts an interpolated `linear_depth` to the 1/z that's kept in the depth buffer.
+    pub fn inv_z(&self, linear_depth: f64) -> f64 {
+        match self {
+            Projection::Perspective => linear_depth,
+            Projection::Orthographic => 1. / linear_depth,
+        }
+    }
+
+    /// The weight given to a vertex's attributes to correct for perspective when they're interpolated across the
+    /// screen. Attributes are interpolated multiplied by their weight, then divided by the interpolated weight.
+    pub fn perspective_weight(&self, z: f64) -> f64 {
+        match self {
+            Projection::Perspective => 1. / z,
+            Projection::Orthographic => 1.,
+        }
+    }
+}
+
+/// The size of the window onto the scene: on the projection plane for a perspective projection, or anywhere along
+/// the camera's z axis for an orthographic one.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Viewport {
+    pub width: f64,
+    pub height: f64,
+    pub projection: Projection,
+}
+
 pub struct Camera {
-    pub position: Vec3,
-    pub orientation: Mat3,
     pub transformation: Mat4,
+    pub viewport: Viewport,
     pub clipping_planes: Vec<Plane>,
 }
 
 impl Camera {
-    pub fn new(position: Vec3, orientation: Mat3, clipping_planes: Vec<Plane>) -> Camera {
-        let transformation = orientation.transpose().to_homogenous_rotation()
-            * (position * -1.).to_homogenous_translation();
-        Camera {
+    /// Creates the camera that renders the view of a scene's camera onto a canvas with the given aspect ratio.
+    pub fn from_scene(camera: &scene::camera::Camera, aspect_ratio: f64) -> Camera {
+        let scene::camera::Camera {
             position,
             orientation,
-            transformation,
+            projection,
+            near,
+            far,
+        } = *camera;
+        match projection {
+            scene::camera::Projection::Perspective { fov } => {
+                Camera::new(position, orientation, fov, aspect_ratio, near, far)
+            }
+            scene::camera::Projection::Orthographic { height } => {
+                Camera::new_orthographic(position, orientation, height, aspect_ratio, near, far)
+            }
+        }
+    }
+
+    /// Creates a perspective camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `fov`: the vertical field of view, in degrees.
+    /// * `aspect_ratio`: the width of the view divided by its height, normally that of the canvas.
+    /// * `near`, `far`: the distances from the camera beyond which objects are clipped.
+    ///
+    pub fn new(
+        position: Vec3,
+        orientation: Mat3,
+        fov: f64,
+        aspect_ratio: f64,
+        near: f64,
+        far: f64,
+    ) -> Camera {
+        let half_height = PROJECTION_PLANE_Z * (fov.to_radians() / 2.).tan();
+        let half_width = half_height * aspect_ratio;
+        let viewport = Viewport {
+            width: 2. * half_width,
+            height: 2. * half_height,
+            projection: Projection::Perspective,
+        };
+
+        // Each side plane passes through the camera and an edge of the viewport, with its normal pointing inwards.
+        let side_plane = |x: f64, y: f64, z: f64| {
+            let normal = Vec3::new(x, y, z);
+            Plane::new(&normal / normal.len(), 0.)
+        };
+        let clipping_planes = vec![
+            Plane::new(Vec3::new(0., 0., 1.), -near),         // Near
+            Plane::new(Vec3::new(0., 0., -1.), far),          // Far
+            side_plane(PROJECTION_PLANE_Z, 0., half_width),   // Left
+            side_plane(-PROJECTION_PLANE_Z, 0., half_width),  // Right
+            side_plane(0., PROJECTION_PLANE_Z, half_height),  // Bottom
+            side_plane(0., -PROJECTION_PLANE_Z, half_height), // Top
+        ];
+
+        Camera::with_viewport(position, orientation, viewport, clipping_planes)
+    }
+
+    /// Creates an orthographic camera.
+    ///
+    /// # Arguments
+    ///
+    /// * `height`: the height of the view, in the same units as the scene.
+    /// * `aspect_ratio`: the width of the view divided by its height, normally that of the canvas.
+    /// * `near`, `far`: the distances from the camera beyond which objects are clipped.
+    ///
+    pub fn new_orthographic(
+        position: Vec3,
+        orientation: Mat3,
+        height: f64,
+        aspect_ratio: f64,
+        near: f64,
+        far: f64,
+    ) -> Camera {
+        let half_height = height / 2.;
+        let half_width = half_height * aspect_ratio;
+        let viewport = Viewport {
+            width: 2. * half_width,
+            height: 2. * half_height,
+            projection: Projection::Orthographic,
+        };
+
+        // The side planes are parallel to the camera's z axis.
+        let clipping_planes = vec![
+            Plane::new(Vec3::new(0., 0., 1.), -near),        // Near
+            Plane::new(Vec3::new(0., 0., -1.), far),         // Far
+            Plane::new(Vec3::new(1., 0., 0.), half_width),   // Left
+            Plane::new(Vec3::new(-1., 0., 0.), half_width),  // Right
+            Plane::new(Vec3::new(0., 1., 0.), half_height),  // Bottom
+            Plane::new(Vec3::new(0., -1., 0.), half_height), // Top
+        ];
+
+        Camera::with_viewport(position, orientation, viewport, clipping_planes)
+    }
+
+    fn with_viewport(
+        position: Vec3,
+        orientation: Mat3,
+        viewport: Viewport,
+        clipping_planes: Vec<Plane>,
+    ) -> Camera {
+        Camera {
+            transformation: Camera::transformation(&position, &orientation),
+            viewport,
             clipping_planes,
         }
     }
+
+    fn transformation(position: &Vec3, orientation: &Mat3) -> Mat4 {
+        orientation.transpose().to_homogenous_rotation()
+            * (position * -1.).to_homogenous_translation()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rasterizer::camera::Camera;
+    use crate::rasterizer::plane::Plane;
+    use crate::scene::camera::DEFAULT_FOV;
+    use crate::vec3::{Mat3, Vec3};
+
+    fn signed_distance(plane: &Plane, point: &Vec3) -> f64 {
+        plane.normal.dot(point) + plane.distance
+    }
+
+    #[test]
+    fn test_clipping_planes() {
+        // A 90° field of view is as tall as it is deep, and an aspect ratio of 2 twice as wide.
+        let camera = Camera::new(Vec3::new(0., 0., 0.), Mat3::identity(), 90., 2., 1., 10.);
+        assert!((camera.viewport.height - 2.).abs() < 1e-9);
+        assert!((camera.viewport.width - 4.).abs() < 1e-9);
+
+        // A point on each face of the frustum, in the order of the planes: near, far, left, right, bottom and top.
+        let faces = [
+            Vec3::new(0.5, 0.5, 1.),
+            Vec3::new(3., -4., 10.),
+            Vec3::new(-4., 1., 2.),
+            Vec3::new(6., -1., 3.),
+            Vec3::new(1., -2., 2.),
+            Vec3::new(-1., 3., 3.),
+        ];
+        for (plane, point) in camera.clipping_planes.iter().zip(faces) {
+            assert!(signed_distance(plane, &point).abs() < 1e-9, "{:?}", point);
+        }
+
+        // A point inside is in front of every plane. A point that's only inside because the view is wider than it's
+        // tall is too, while one as far above the center is behind the top plane.
+        for point in [Vec3::new(0.5, -0.5, 5.), Vec3::new(-2.5, 0., 1.5)] {
+            for plane in camera.clipping_planes.iter() {
+                assert!(signed_distance(plane, &point) > 0., "{:?}", point);
+            }
+        }
+        assert!(signed_distance(&camera.clipping_planes[5], &Vec3::new(0., 2.5, 1.5)) < 0.);
+    }
+
+    #[test]
+    fn test_default_fov() {
+        // The default field of view makes the viewport as tall as it is far from the camera.
+        let camera = Camera::new(
+            Vec3::new(0., 0., 0.),
+            Mat3::identity(),
+            DEFAULT_FOV,
+            1.,
+            1.,
+            10.,
+        );
+        assert!((camera.viewport.height - 1.).abs() < 1e-12);
+        assert!((camera.viewport.width - 1.).abs() < 1e-12);
+    }
 }
@@ -1,81 +1,193 @@
 use crate::rasterizer::camera::Camera;
-use crate::rasterizer::light::{Light, Scatter};
+use crate::rasterizer::instance::Instance;
 use crate::rasterizer::model::Model;
-use crate::rasterizer::point::Point;
-use crate::rasterizer::scene::Scene;
-use crate::rasterizer::shading::ShadingModel;
-use crate::rasterizer::texture::Texture;
+use crate::rasterizer::point::{Point, SubpixelPoint};
+use crate::rasterizer::rasterization;
+use crate::rasterizer::rasterization::{Multisampling, Rasterization};
+use crate::rasterizer::shading::{Scanline, ShadingContext, ShadingModel};
+use crate::rasterizer::surface::NormalMap;
 use crate::rasterizer::triangle::Triangle;
 use crate::rasterizer::util;
-use crate::vec3::{Color, Mat3, Vec3, Vec4};
+use crate::renderer::Statistics;
+use crate::scene::light::Light;
+use crate::scene::node::Shape;
+use crate::scene::texture::Texture;
+use crate::scene::Scene;
+use crate::vec3::{Color, Mat3, Mat4, Vec3, Vec4};
 use itertools::Itertools;
+use std::collections::HashMap;
 
 pub struct Canvas {
     pub height: i64,
     pub width: i64,
-    pub pixels: Vec<u8>,
+    pub samples: Vec<f32>,
     pub depth_buffer: Vec<f64>,
     pub shading_model: ShadingModel,
+    pub rasterization: Rasterization,
+    pub multisampling: Multisampling,
 }
 
 impl Canvas {
-    pub fn new(height: usize, width: usize, shading_model: ShadingModel) -> Canvas {
+    pub fn new(
+        height: usize,
+        width: usize,
+        shading_model: ShadingModel,
+        rasterization: Rasterization,
+        multisampling: Multisampling,
+    ) -> Canvas {
         let dim = width * height;
-        let pixel_bytes = dim * 4;
-        let mut pixels = Vec::with_capacity(pixel_bytes);
-        for _i in 0..pixel_bytes {
-            pixels.push(0);
+
+        // Each sample has its own color and depth, which are resolved into the framebuffer once the scene is rendered.
+        let sample_count = dim * multisampling.samples();
+        let mut samples = Vec::with_capacity(sample_count * 4);
+        for _i in 0..(sample_count * 4) {
+            samples.push(0.);
         }
 
-        let mut depth_buffer = Vec::with_capacity(dim);
-        for _i in 0..dim {
+        let mut depth_buffer = Vec::with_capacity(sample_count);
+        for _i in 0..sample_count {
             depth_buffer.push(f64::NEG_INFINITY);
         }
 
         Canvas {
             height: height as i64,
             width: width as i64,
-            pixels,
+            samples,
             depth_buffer,
             shading_model,
+            rasterization,
+            multisampling,
         }
     }
 
-    fn put_pixel(&mut self, x: i64, y: i64, inv_z: f64, color: &Color) {
+    /// Clears the samples to transparent black, ready to render another frame.
+    pub fn clear(&mut self) {
+        self.samples.fill(0.);
+        self.depth_buffer.fill(f64::NEG_INFINITY);
+    }
+
+    fn pixel_index(&self, x: i64, y: i64) -> Option<usize> {
         let x = self.width / 2 + x;
         let y = self.height / 2 - y - 1;
 
         if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            let depth_offset = (y * self.width + x) as usize;
-            if inv_z > self.depth_buffer[depth_offset] {
-                let pixel_offset = (y * self.width * 4 + x * 4) as usize;
-                self.pixels[pixel_offset] = color[0].clamp(0., 255.) as u8;
-                self.pixels[pixel_offset + 1] = color[1].clamp(0., 255.) as u8;
-                self.pixels[pixel_offset + 2] = color[2].clamp(0., 255.) as u8;
-                self.pixels[pixel_offset + 3] = 255;
-                self.depth_buffer[depth_offset] = inv_z;
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn put_pixel(&mut self, x: i64, y: i64, inv_z: f64, color: &Color) {
+        if let Some(pixel) = self.pixel_index(x, y) {
+            for sample in 0..self.multisampling.samples() {
+                self.put_sample(pixel, sample, inv_z, color);
+            }
+        }
+    }
+
+    fn put_sample(&mut self, pixel: usize, sample: usize, inv_z: f64, color: &Color) {
+        let depth_offset = pixel * self.multisampling.samples() + sample;
+        if inv_z > self.depth_buffer[depth_offset] {
+            let sample_offset = depth_offset * 4;
+            // Colors are kept linear and unclamped, with 1 for white, so highlights can be tone mapped later.
+            self.samples[sample_offset] = (color[0] / 255.) as f32;
+            self.samples[sample_offset + 1] = (color[1] / 255.) as f32;
+            self.samples[sample_offset + 2] = (color[2] / 255.) as f32;
+            self.samples[sample_offset + 3] = 1.;
+            self.depth_buffer[depth_offset] = inv_z;
+        }
+    }
+
+    /// Averages the samples of each pixel into the framebuffer's `colors`. A partially covered pixel is made partially
+    /// transparent.
+    fn resolve(&self, colors: &mut [f32]) {
+        let n = self.multisampling.samples();
+        for pixel in 0..(self.width * self.height) as usize {
+            let mut color = [0f32; 3];
+            let mut covered = 0;
+            for sample in 0..n {
+                let sample_offset = (pixel * n + sample) * 4;
+                if self.samples[sample_offset + 3] == 1. {
+                    for c in 0..3 {
+                        color[c] += self.samples[sample_offset + c];
+                    }
+                    covered += 1;
+                }
+            }
+
+            if covered > 0 {
+                let pixel_offset = pixel * 4;
+                for c in 0..3 {
+                    colors[pixel_offset + c] = color[c] / covered as f32;
+                }
+                colors[pixel_offset + 3] = covered as f32 / n as f32;
             }
         }
     }
 
+    /// Renders the triangle unless it faces away from the camera, returning whether it was rendered.
     fn render_triangle(
         &mut self,
         triangle: &Triangle,
-        vertices: &Vec<Vec3>,
-        projected: &Vec<Point>,
-        textures: &Vec<Texture>,
-        camera: &Camera,
-        lights: &Vec<Light>,
-        scatter: &Scatter,
-        orientation: &Mat3,
-    ) {
+        vertices: &[Vec3],
+        projected: &[Point],
+        normal_matrix: &Option<Mat3>,
+        tangent_matrix: &Mat3,
+        context: &ShadingContext,
+    ) -> bool {
         // Backface Culling
         let normal = triangle.normal(vertices);
         let center = -triangle.center(vertices);
         if center.dot(&normal) <= 0. {
-            return;
+            return false;
         }
 
+        let vertex_normals = match (triangle.normals, normal_matrix) {
+            (Some(normals), Some(normal_matrix)) => Some(normals.map(|n| {
+                let n = normal_matrix * n;
+                &n / n.len()
+            })),
+            _ => None,
+        };
+
+        let normals = match &vertex_normals {
+            None => [&normal, &normal, &normal],
+            Some(normals) => [&normals[0], &normals[1], &normals[2]],
+        };
+        let normal_map = triangle
+            .normal_map
+            .as_ref()
+            .map(|normal_map| normal_map.transformed(tangent_matrix));
+
+        match self.rasterization {
+            Rasterization::Scanline => self.render_triangle_scanline(
+                triangle,
+                vertices,
+                projected,
+                normals,
+                normal_map.as_ref(),
+                context,
+            ),
+            Rasterization::EdgeFunction => self.render_triangle_edge_function(
+                triangle,
+                vertices,
+                normals,
+                normal_map.as_ref(),
+                context,
+            ),
+        }
+        true
+    }
+
+    fn render_triangle_scanline(
+        &mut self,
+        triangle: &Triangle,
+        vertices: &[Vec3],
+        projected: &[Point],
+        normals: [&Vec3; 3],
+        normal_map: Option<&NormalMap>,
+        context: &ShadingContext,
+    ) {
         // Find the points along the sides of the triangle.
         let indexes @ [i0, i1, i2] = triangle.sorted_indexes_by_y(projected);
         let v0 = &vertices[triangle.vertex_indices[i0]];
@@ -90,7 +202,10 @@ impl Canvas {
 
         let x_edges =
             util::edge_interpolate(p0.y, p0.x as f64, p1.y, p1.x as f64, p2.y, p2.x as f64);
-        let iz_edges = util::edge_interpolate(p0.y, 1. / v0[2], p1.y, 1. / v1[2], p2.y, 1. / v2[2]);
+        let projection = context.camera.viewport.projection;
+        let depth = vertices.map(|v| projection.linear_depth(v[2]));
+        let depth_edges = util::edge_interpolate(p0.y, depth[0], p1.y, depth[1], p2.y, depth[2]);
+        let perspective_weights = vertices.map(|v| projection.perspective_weight(v[2]));
 
         // Determine which side is the left and which is the right.
         let midpoint = x_edges[0].len() / 2;
@@ -100,103 +215,201 @@ impl Canvas {
             (1, 0)
         };
 
-        let vertex_normals = triangle.normals.map(|normals| {
-            let transformation = camera.orientation.transpose().to_homogenous_rotation()
-                * orientation.to_homogenous_rotation();
-            [
-                (&transformation * normals[i0].to_vec4(1.0)).to_vec3(),
-                (&transformation * normals[i1].to_vec4(1.0)).to_vec3(),
-                (&transformation * normals[i2].to_vec4(1.0)).to_vec3(),
-            ]
+        let normals = [normals[i0], normals[i1], normals[i2]];
+        // The normal map's corners are put in the same order, by taking each of them whole.
+        let normal_map = normal_map.map(|normal_map| {
+            normal_map.clipped(indexes.map(|i| {
+                let mut weights = [0.; 3];
+                weights[i] = 1.;
+                weights
+            }))
         });
-
-        let normals = match &vertex_normals {
-            None => [&normal, &normal, &normal],
-            Some(normals) => [&normals[0], &normals[1], &normals[2]],
-        };
-
-        let shader = self
-            .shading_model
-            .shader(vertices, points, normals, camera, lights, scatter);
-        let color_generator = triangle.surface.color_generator(indexes, vertices, points);
+        let shader =
+            self.shading_model
+                .shader(vertices, points, normals, normal_map.as_ref(), context);
+        let color_generator =
+            triangle
+                .surface
+                .color_generator(indexes, perspective_weights, points);
 
         // Draw the horizontal line segments.
         for (yi, &(y, x_left)) in x_edges[left].iter().enumerate() {
             let x_left = x_left.floor() as i64;
             let x_right = x_edges[right][yi].1.ceil() as i64;
-            let iz_left = iz_edges[left][yi].1;
-            let iz_right = iz_edges[right][yi].1;
-            let iz_segment = util::interpolate(x_left, iz_left, x_right, iz_right)
-                .map(|(_x, inv_z)| inv_z)
+            let depth_left = depth_edges[left][yi].1;
+            let depth_right = depth_edges[right][yi].1;
+            let iz_segment = util::interpolate(x_left, depth_left, x_right, depth_right)
+                .map(|(_x, depth)| projection.inv_z(depth))
                 .collect_vec();
 
             let x = [x_edges[0][yi].1, x_edges[1][yi].1];
-            let colors = color_generator.colors(left, right, yi, x, &iz_segment, textures);
-            let intensities = shader.intensities(
+            let colors = color_generator.colors(left, right, yi, x, context.textures);
+            let scanline = Scanline {
                 left,
                 right,
-                yi,
+                i: yi,
                 y,
                 x,
-                &iz_segment,
-                self.width,
-                self.height,
-                camera,
-                lights,
-                scatter,
-            );
+                inv_z: &iz_segment,
+            };
+            let intensities = shader.intensities(&scanline, self.width, self.height, context);
 
             for (xi, x) in (x_left..(x_right + 1)).enumerate() {
-                self.put_pixel(x, y, iz_segment[xi], &(colors[xi] * intensities[xi]));
+                self.put_pixel(
+                    x,
+                    y,
+                    iz_segment[xi],
+                    &intensities[xi].shade(colors[xi], context.material.specular),
+                );
             }
         }
     }
 
-    fn project(&self, v: &Vec4) -> Point {
-        util::project_vertex(v, self.width, self.height)
+    fn render_triangle_edge_function(
+        &mut self,
+        triangle: &Triangle,
+        vertices: &[Vec3],
+        normals: [&Vec3; 3],
+        normal_map: Option<&NormalMap>,
+        context: &ShadingContext,
+    ) {
+        let vertices = triangle.vertex_indices.map(|i| &vertices[i]);
+        let points = vertices.map(|v| self.project_subpixel(&v.to_vec4(1.0), context.camera));
+        let projection = context.camera.viewport.projection;
+        let depth = vertices.map(|v| projection.linear_depth(v[2]));
+        let perspective_weights = vertices.map(|v| projection.perspective_weight(v[2]));
+        let shader = self
+            .shading_model
+            .barycentric_shader(vertices, normals, normal_map, context);
+
+        let x_range = (-self.width / 2, self.width - self.width / 2 - 1);
+        let y_range = (self.height / 2 - self.height, self.height / 2 - 1);
+        let n = self.multisampling.samples();
+        rasterization::rasterize_edge_function(
+            [&points[0], &points[1], &points[2]],
+            x_range,
+            y_range,
+            self.multisampling,
+            |x, y, weights, samples| {
+                let pixel = match self.pixel_index(x, y) {
+                    Some(pixel) => pixel,
+                    None => return,
+                };
+
+                // The weights are linear in screen space, so they can interpolate depth directly.
+                let iz = |w: [f64; 3]| {
+                    projection.inv_z(depth[0] * w[0] + depth[1] * w[1] + depth[2] * w[2])
+                };
+                let visible = samples
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(sample, weights)| weights.map(|w| (sample, iz(w))))
+                    .filter(|&(sample, inv_z)| inv_z > self.depth_buffer[pixel * n + sample])
+                    .collect_vec();
+                if visible.is_empty() {
+                    return;
+                }
+
+                // Shade once per pixel, at its center, correcting the weights for perspective.
+                let weights = [0, 1, 2].map(|i| weights[i] * perspective_weights[i]);
+                let total = weights[0] + weights[1] + weights[2];
+                let weights = weights.map(|w| w / total);
+                let color = shader.intensity(weights, context).shade(
+                    triangle.surface.color_at(weights, context.textures),
+                    context.material.specular,
+                );
+                for (sample, inv_z) in visible {
+                    self.put_sample(pixel, sample, inv_z, &color);
+                }
+            },
+        );
+    }
+
+    fn project(&self, v: &Vec4, camera: &Camera) -> Point {
+        util::project_vertex(v, &camera.viewport, self.width, self.height)
     }
 
-    pub fn render_scene(&mut self, scene: &Scene) {
-        for instance in scene.instances.iter() {
-            if let Some(model) = instance
-                .transform_and_clip(&scene.camera.transformation, &scene.camera.clipping_planes)
+    fn project_subpixel(&self, v: &Vec4, camera: &Camera) -> SubpixelPoint {
+        util::project_vertex_subpixel(v, &camera.viewport, self.width, self.height)
+    }
+
+    /// Renders the scene into the framebuffer's colors, which have to be cleared first along with the canvas. The
+    /// models of its shapes are taken from `models`, and built and added to it when they aren't there yet.
+    pub fn render_scene(
+        &mut self,
+        scene: &Scene,
+        models: &mut HashMap<(Shape, usize), Model>,
+        colors: &mut [f32],
+    ) -> Statistics {
+        let mut statistics = Statistics::default();
+        let camera = Camera::from_scene(&scene.camera, self.width as f64 / self.height as f64);
+
+        // Vertices are shaded in the camera's space, so the lights are moved into it too.
+        let lights = scene
+            .lights
+            .iter()
+            .map(|light| light.transformed(&camera.transformation))
+            .collect_vec();
+
+        for object in scene.objects().iter() {
+            let material = &scene.materials()[object.material];
+            let model = models
+                .entry((object.shape, object.material))
+                .or_insert_with(|| {
+                    Model::from_shape(&object.shape, scene.meshes(), material, scene.textures())
+                });
+            let instance = Instance::new(model, object.transformation);
+            if let Some(model) =
+                instance.transform_and_clip(&camera.transformation, &camera.clipping_planes)
             {
-                self.render_model(
-                    &model,
-                    &scene.camera,
-                    &scene.lights,
-                    &instance.rotation,
-                    &scene.textures,
-                );
+                let model_view = &camera.transformation * &instance.transformation;
+                statistics.objects += 1;
+                statistics.triangles +=
+                    self.render_model(&model, &camera, &lights, &model_view, scene.textures());
             }
         }
+        self.resolve(colors);
+        statistics
     }
 
+    /// Renders the model's triangles, returning how many weren't culled. Its vertices have to be in the camera's space
+    /// already, while its normals and tangents are moved there by `model_view`.
     fn render_model(
         &mut self,
         model: &Model,
         camera: &Camera,
-        lights: &Vec<Light>,
-        orientation: &Mat3,
-        textures: &Vec<Texture>,
-    ) {
+        lights: &[Light],
+        model_view: &Mat4,
+        textures: &[Texture],
+    ) -> usize {
+        let normal_matrix = model_view.normal_matrix();
+        let tangent_matrix = model_view.to_mat3();
         let projected = model
             .vertices
             .iter()
-            .map(|v| self.project(&v.to_vec4(1.0)))
+            .map(|v| self.project(&v.to_vec4(1.0), camera))
             .collect_vec();
 
+        let context = ShadingContext {
+            camera,
+            lights,
+            material: &model.material,
+            textures,
+        };
+
+        let mut drawn = 0;
         for triangle in model.triangles.iter() {
-            self.render_triangle(
+            if self.render_triangle(
                 triangle,
                 &model.vertices,
                 &projected,
-                textures,
-                camera,
-                lights,
-                &model.scatter,
-                orientation,
-            );
+                &normal_matrix,
+                &tangent_matrix,
+                &context,
+            ) {
+                drawn += 1;
+            }
         }
+        drawn
     }
 }
@@ -1,29 +1,19 @@
 use crate::rasterizer::model::Model;
 use crate::rasterizer::plane::Plane;
 use crate::rasterizer::triangle::Triangle;
-use crate::vec3::{Mat3, Mat4, Vec3};
+use crate::vec3::{Mat4, Vec3};
 use itertools::Itertools;
 
+/// A model placed in the world, ready to be rendered.
 pub struct Instance<'a> {
     pub model: &'a Model,
-    pub scale: f64,
-    pub rotation: Mat3,
-    pub translation: Vec3,
     pub transformation: Mat4,
 }
 
 impl<'a> Instance<'a> {
-    pub fn new(model: &'a Model, scale: f64, rotation: Mat3, translation: Vec3) -> Instance<'a> {
-        let scaling_mat4 = Mat4::new_homogeneous_scaling_matrix(scale);
-        let rotation_mat4 = rotation.to_homogenous_rotation();
-        let translation_mat4 = translation.to_homogenous_translation();
-        let transformation = translation_mat4 * rotation_mat4 * scaling_mat4;
-
+    pub fn new(model: &'a Model, transformation: Mat4) -> Instance<'a> {
         Instance {
             model,
-            scale,
-            rotation,
-            translation,
             transformation,
         }
     }
@@ -35,10 +25,14 @@ impl<'a> Instance<'a> {
     ) -> Option<Model> {
         let transformation = camera_transformation * &self.transformation;
         let bounding_sphere_center = &transformation * self.model.bounds_center.to_vec4(1.0);
-        let bounding_sphere_radius = self.model.bounds_radius * self.scale;
+
+        // Rotation and translation don't change the radius, but it has to grow with the greatest scale to enclose the
+        // model.
+        let max_scale = self.transformation.to_mat3().max_stretch();
+        let bounding_sphere_radius = self.model.bounds_radius * max_scale;
 
         let outside_any_clipping_plane = clipping_planes.iter().any(|plane| {
-            let distance = bounding_sphere_center.dot(&plane.normal) + plane.distance;
+            let distance = bounding_sphere_center.to_vec3().dot(&plane.normal) + plane.distance;
             distance < -bounding_sphere_radius
         });
         if outside_any_clipping_plane {
@@ -60,7 +54,7 @@ impl<'a> Instance<'a> {
                 triangles = new_triangles;
             }
 
-            Some(Model::new(vertices, triangles, self.model.scatter))
+            Some(Model::new(vertices, triangles, self.model.material))
         }
     }
 
@@ -77,6 +71,20 @@ impl<'a> Instance<'a> {
             .collect_vec();
         let in_count = in_plane.iter().filter(|&&d| d > 0.).count();
 
+        // Clipped vertices are described by their weights in the original triangle, so that their normals and texture
+        // coordinates can be interpolated. Vertices are taken in their original order to preserve the winding.
+        let corner = |i: usize| {
+            let mut weights = [0.; 3];
+            weights[i] = 1.;
+            weights
+        };
+        let between = |i: usize, j: usize, t: f64| {
+            let mut weights = [0.; 3];
+            weights[i] = 1. - t;
+            weights[j] = t;
+            weights
+        };
+
         if in_count == 1 {
             // Let A be the vertex with a positive distance
             // compute B' = Intersection(AB, plane)
@@ -86,20 +94,24 @@ impl<'a> Instance<'a> {
                 .iter()
                 .position_max_by(|&a, &b| a.partial_cmp(b).unwrap())
                 .unwrap();
-            let (b_index, c_index) = (0..3).filter(|&i| i != a_index).collect_tuple().unwrap();
-            let b_prime = plane.segment_intersection(&vertices[v[a_index]], &vertices[v[b_index]]);
-            let c_prime = plane.segment_intersection(&vertices[v[a_index]], &vertices[v[c_index]]);
+            let (b_index, c_index) = ((a_index + 1) % 3, (a_index + 2) % 3);
+            let (b_prime, tb) =
+                plane.segment_intersection(&vertices[v[a_index]], &vertices[v[b_index]]);
+            let (c_prime, tc) =
+                plane.segment_intersection(&vertices[v[a_index]], &vertices[v[c_index]]);
 
             let b_prime_index = vertices.len();
             let c_prime_index = vertices.len() + 1;
             vertices.push(b_prime);
             vertices.push(c_prime);
 
-            triangles.push(Triangle::new_no_normals(
-                v[a_index],
-                c_prime_index,
-                b_prime_index,
-                triangle.surface,
+            triangles.push(triangle.clipped(
+                [v[a_index], b_prime_index, c_prime_index],
+                [
+                    corner(a_index),
+                    between(a_index, b_index, tb),
+                    between(a_index, c_index, tc),
+                ],
             ))
         } else if in_count == 2 {
             // Let C be the vertex with a negative distance
@@ -110,30 +122,103 @@ impl<'a> Instance<'a> {
                 .iter()
                 .position_min_by(|&a, &b| a.partial_cmp(b).unwrap())
                 .unwrap();
-            let (a_index, b_index) = (0..3).filter(|&i| i != c_index).collect_tuple().unwrap();
+            let (a_index, b_index) = ((c_index + 1) % 3, (c_index + 2) % 3);
 
-            let a_prime = plane.segment_intersection(&vertices[v[a_index]], &vertices[v[c_index]]);
-            let b_prime = plane.segment_intersection(&vertices[v[b_index]], &vertices[v[c_index]]);
+            let (a_prime, ta) =
+                plane.segment_intersection(&vertices[v[a_index]], &vertices[v[c_index]]);
+            let (b_prime, tb) =
+                plane.segment_intersection(&vertices[v[b_index]], &vertices[v[c_index]]);
 
             let a_prime_index = vertices.len();
             let b_prime_index = vertices.len() + 1;
             vertices.push(a_prime);
             vertices.push(b_prime);
 
-            triangles.push(Triangle::new_no_normals(
-                v[a_index],
-                v[b_index],
-                a_prime_index,
-                triangle.surface.clone(),
+            let a_prime_weights = between(a_index, c_index, ta);
+            triangles.push(triangle.clipped(
+                [v[a_index], v[b_index], a_prime_index],
+                [corner(a_index), corner(b_index), a_prime_weights],
             ));
-            triangles.push(Triangle::new_no_normals(
-                a_prime_index,
-                v[b_index],
-                b_prime_index,
-                triangle.surface.clone(),
+            triangles.push(triangle.clipped(
+                [a_prime_index, v[b_index], b_prime_index],
+                [
+                    a_prime_weights,
+                    corner(b_index),
+                    between(b_index, c_index, tb),
+                ],
             ));
         } else if in_count == 3 {
             triangles.push(triangle);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rasterizer::camera::Camera;
+    use crate::rasterizer::instance::Instance;
+    use crate::rasterizer::model::Model;
+    use crate::scene::examples::Example;
+    use crate::scene::node::Shape;
+    use crate::vec3::{Mat3, Vec3};
+
+    fn cube() -> Model {
+        let scene = Example::Crates.scene();
+        Model::from_shape(
+            &Shape::Mesh(0),
+            scene.meshes(),
+            &scene.materials()[0],
+            scene.textures(),
+        )
+    }
+
+    #[test]
+    fn test_stretched_bounds() {
+        let camera = Camera::new(Vec3::new(0., 0., 0.), Mat3::identity(), 90., 1., 1., 100.);
+        let cube = cube();
+        let at = |scale: Vec3| {
+            Vec3::new(12., 0., 5.).to_homogenous_translation() * scale.to_homogenous_scale()
+        };
+
+        // The cube is well to the right of the view, and only reaches into it when it's stretched along x.
+        let instance = Instance::new(&cube, at(Vec3::new(1., 1., 1.)));
+        assert!(instance
+            .transform_and_clip(&camera.transformation, &camera.clipping_planes)
+            .is_none());
+        let instance = Instance::new(&cube, at(Vec3::new(10., 1., 1.)));
+        let clipped = instance
+            .transform_and_clip(&camera.transformation, &camera.clipping_planes)
+            .unwrap();
+        assert!(!clipped.triangles.is_empty());
+    }
+
+    #[test]
+    fn test_stretched_normals() {
+        let camera = Camera::new(Vec3::new(0., 0., 0.), Mat3::identity(), 90., 1., 1., 100.);
+        let cube = cube();
+
+        // Stretching a turned cube shears its sides, which tilts their normals unless they're moved by the normal
+        // matrix.
+        let transformation = Vec3::new(0., 0., 20.).to_homogenous_translation()
+            * Vec3::new(3., 1., 1.).to_homogenous_scale()
+            * Mat3::new_oz_rotation_matrix(30.).to_homogenous_rotation();
+        let instance = Instance::new(&cube, transformation);
+        let model = instance
+            .transform_and_clip(&camera.transformation, &camera.clipping_planes)
+            .unwrap();
+        let model_view = camera.transformation * instance.transformation;
+        let normal_matrix = model_view.normal_matrix().unwrap();
+
+        assert_eq!(model.triangles.len(), 12);
+        for triangle in model.triangles.iter() {
+            let [v0, v1, v2] = triangle.vertex_indices.map(|v| model.vertices[v]);
+            let (e1, e2) = (v1 - &v0, v2 - &v0);
+            let normal = &normal_matrix * triangle.normals.unwrap()[0];
+            let normal = normal / normal.len();
+            assert!(normal.dot(&(e1 / e1.len())).abs() < 1e-9);
+            assert!(normal.dot(&(e2 / e2.len())).abs() < 1e-9);
+            // And they still point out of the cube.
+            assert!(normal.dot(&e1.cross_product(&e2)) > 0.);
+        }
+    }
+}
@@ -1,238 +1,274 @@
-use crate::rasterizer::camera::Camera;
 use crate::rasterizer::canvas::Canvas;
-use crate::rasterizer::instance::Instance;
-use crate::rasterizer::light::{Light, Scatter};
 use crate::rasterizer::model::Model;
-use crate::rasterizer::plane::Plane;
-use crate::rasterizer::scene::Scene;
-use crate::rasterizer::shading::ShadingModel::{Flat, Gouraud, Phong};
-use crate::rasterizer::surface::Surface;
-use crate::rasterizer::texture::Texture;
-use crate::rasterizer::triangle::Triangle;
-use crate::rasterizer::util::PROJECTION_PLANE_Z;
-use crate::utils;
-use crate::vec3::{Color, Mat3, Vec3};
-use std::io::Bytes;
-use wasm_bindgen::prelude::*;
-
-const CRATE_BYTES: &[u8; 318447] = include_bytes!("crate-texture.jpg");
-
-#[wasm_bindgen]
-pub fn rasterizer(canvas_height: usize, canvas_width: usize) -> Vec<u8> {
-    utils::set_panic_hook();
-    let mut canvas = Canvas::new(canvas_height, canvas_width, Phong);
-    let crate_texture = Texture::from_bytes(CRATE_BYTES).unwrap();
-
-    // let black = Color::new(0., 0., 0.);
-    // let red = Color::new(255., 0., 0.);
-    // let green = Color::new(0., 255., 0.);
-    // let blue = Color::new(0., 0., 255.);
-    // let yellow = Color::new(255., 255., 0.);
-    // let purple = Color::new(255., 0., 255.);
-    // let cyan = Color::new(0., 255., 255.);
-
-    let scatter = Scatter::Specular { shininess: 50.0 };
-    let wood_upper = Surface::Texture {
-        index: 0,
-        uvs: [(0., 0.), (1., 0.), (1., 1.)],
-    };
-    let wood_lower = Surface::Texture {
-        index: 0,
-        uvs: [(0., 0.), (1., 1.), (0., 1.)],
-    };
-    let cube_model = Model::new(
-        vec![
-            Vec3::new(1., 1., 1.),
-            Vec3::new(-1., 1., 1.),
-            Vec3::new(-1., -1., 1.),
-            Vec3::new(1., -1., 1.),
-            Vec3::new(1., 1., -1.),
-            Vec3::new(-1., 1., -1.),
-            Vec3::new(-1., -1., -1.),
-            Vec3::new(1., -1., -1.),
-        ],
-        vec![
-            Triangle::new(
-                0,
-                1,
-                2,
-                // Surface::Color(red),
-                wood_upper.clone(),
-                Vec3::new(0., 0., 1.),
-                Vec3::new(0., 0., 1.),
-                Vec3::new(0., 0., 1.),
-            ),
-            Triangle::new(
-                0,
-                2,
-                3,
-                // Surface::Color(red),
-                wood_lower.clone(),
-                Vec3::new(0., 0., 1.),
-                Vec3::new(0., 0., 1.),
-                Vec3::new(0., 0., 1.),
-            ),
-            Triangle::new(
-                1,
-                5,
-                6,
-                // Surface::Color(yellow),
-                wood_upper.clone(),
-                Vec3::new(-1., 0., 0.),
-                Vec3::new(-1., 0., 0.),
-                Vec3::new(-1., 0., 0.),
-            ),
-            Triangle::new(
-                1,
-                6,
-                2,
-                // Surface::Color(yellow),
-                wood_lower.clone(),
-                Vec3::new(-1., 0., 0.),
-                Vec3::new(-1., 0., 0.),
-                Vec3::new(-1., 0., 0.),
-            ),
-            Triangle::new(
-                2,
-                6,
-                7,
-                // Surface::Color(cyan),
-                // wood_upper.clone(),
-                wood_lower.clone(),
-                Vec3::new(0., -1., 0.),
-                Vec3::new(0., -1., 0.),
-                Vec3::new(0., -1., 0.),
-            ),
-            Triangle::new(
-                2,
-                7,
-                3,
-                // Surface::Color(cyan),
-                wood_lower.clone(),
-                Vec3::new(0., -1., 0.),
-                Vec3::new(0., -1., 0.),
-                Vec3::new(0., -1., 0.),
-            ),
-            Triangle::new(
-                4,
-                0,
-                3,
-                // Surface::Color(green),
-                wood_upper.clone(),
-                Vec3::new(1., 0., 0.),
-                Vec3::new(1., 0., 0.),
-                Vec3::new(1., 0., 0.),
-            ),
-            Triangle::new(
-                4,
-                1,
-                0,
-                // Surface::Color(purple),
-                wood_upper.clone(),
-                Vec3::new(0., 1., 0.),
-                Vec3::new(0., 1., 0.),
-                Vec3::new(0., 1., 0.),
-            ),
-            Triangle::new(
-                4,
-                3,
-                7,
-                // Surface::Color(green),
-                wood_lower.clone(),
-                Vec3::new(1., 0., 0.),
-                Vec3::new(1., 0., 0.),
-                Vec3::new(1., 01., 0.),
-            ),
-            Triangle::new(
-                4,
-                5,
-                1,
-                // Surface::Color(purple),
-                wood_lower.clone(),
-                Vec3::new(0., 1., 0.),
-                Vec3::new(0., 1., 0.),
-                Vec3::new(0., 1., 0.),
-            ),
-            Triangle::new(
-                5,
-                4,
-                7,
-                // Surface::Color(blue),
-                wood_upper.clone(),
-                Vec3::new(0., 0., -1.),
-                Vec3::new(0., 0., -1.),
-                Vec3::new(0., 0., -1.),
-            ),
-            Triangle::new(
-                5,
-                7,
-                6,
-                // Surface::Color(blue),
-                wood_lower.clone(),
-                Vec3::new(0., 0., -1.),
-                Vec3::new(0., 0., -1.),
-                Vec3::new(0., 0., -1.),
-            ),
-        ],
-        scatter,
-    );
-    // let sphere = Model::make_sphere(15, Surface::Color(green), scatter);
-
-    let sqrt_2 = 2.0_f64.sqrt();
-    let camera = Camera::new(
-        Vec3::new(-3., 1.0, 2.0),
-        Mat3::new_oy_rotation_matrix(-30.),
-        vec![
-            Plane::new(Vec3::new(0., 0., 1.), -PROJECTION_PLANE_Z), // Near
-            Plane::new(Vec3::new(sqrt_2, 0., sqrt_2), 0.),          // Left
-            Plane::new(Vec3::new(-sqrt_2, 0., sqrt_2), 0.),         // Right
-            Plane::new(Vec3::new(0., sqrt_2, sqrt_2), 0.),          // Bottom
-            Plane::new(Vec3::new(0., -sqrt_2, sqrt_2), 0.),         // Top
-        ],
-    );
-    let scene = Scene {
-        camera,
-        instances: vec![
-            Instance::new(&cube_model, 0.75, Mat3::identity(), Vec3::new(-1.5, 0., 7.)),
-            Instance::new(
-                &cube_model,
-                1.0,
-                Mat3::new_oy_rotation_matrix(195.),
-                Vec3::new(1.25, 2.5, 7.5),
+use crate::rasterizer::rasterization::{Multisampling, Rasterization};
+use crate::rasterizer::shading::ShadingModel;
+use crate::renderer::{Framebuffer, Renderer, SceneCache, Statistics};
+use crate::scene::node::Shape;
+use crate::scene::Scene;
+use std::collections::HashMap;
+
+/// Renders scenes by projecting their triangles onto the screen and filling them in.
+pub struct Rasterizer {
+    shading_model: ShadingModel,
+    rasterization: Rasterization,
+    multisampling: Multisampling,
+    // The samples and depth buffer, kept between frames of the same size.
+    canvas: Option<Canvas>,
+    // The model of each shape made of each material, in its own space.
+    models: SceneCache<HashMap<(Shape, usize), Model>>,
+}
+
+impl Rasterizer {
+    pub fn new(
+        shading_model: ShadingModel,
+        rasterization: Rasterization,
+        multisampling: Multisampling,
+    ) -> Rasterizer {
+        Rasterizer {
+            shading_model,
+            rasterization,
+            multisampling,
+            canvas: None,
+            models: SceneCache::default(),
+        }
+    }
+}
+
+impl Default for Rasterizer {
+    fn default() -> Rasterizer {
+        Rasterizer::new(
+            ShadingModel::Phong,
+            Rasterization::EdgeFunction,
+            Multisampling::X4,
+        )
+    }
+}
+
+impl Renderer for Rasterizer {
+    fn render(&mut self, scene: &Scene, framebuffer: &mut Framebuffer) -> Statistics {
+        let (height, width) = (framebuffer.height, framebuffer.width);
+        let canvas = match self.canvas.take() {
+            Some(canvas) if canvas.height == height as i64 && canvas.width == width as i64 => {
+                canvas
+            }
+            _ => Canvas::new(
+                height,
+                width,
+                self.shading_model,
+                self.rasterization,
+                self.multisampling,
             ),
-            // Instance::new(&sphere, 1.5, Mat3::identity(), Vec3::new(1.75, -0.5, 7.)),
-            // Instance::new(
-            //     &cube_model,
-            //     1.0,
-            //     Mat3::new_oy_rotation_matrix(195.),
-            //     Vec3::new(0., 0., -10.),
-            // ),
-            Instance::new(&cube_model, 1.0, Mat3::identity(), Vec3::new(3., -1.5, 6.5)),
-        ],
-        lights: vec![
-            Light::Ambient { intensity: 0.2 },
-            Light::Directional {
-                intensity: 0.2,
-                direction: Vec3::new(-1., 0., 1.),
-            },
-            Light::Point {
-                intensity: 0.6,
-                position: Vec3::new(-3., 2., -10.),
-            },
-        ],
-        textures: vec![crate_texture],
-    };
-
-    canvas.render_scene(&scene);
-    canvas.pixels
+        };
+        let canvas = self.canvas.insert(canvas);
+
+        canvas.clear();
+        framebuffer.clear();
+        canvas.render_scene(scene, self.models.get(scene), &mut framebuffer.colors)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::rasterizer::main::rasterizer;
+    use crate::color::ToneMapping;
+    use crate::golden::assert_matches_golden;
+    use crate::rasterizer::main::Rasterizer;
+    use crate::rasterizer::rasterization::{Multisampling, Rasterization};
+    use crate::rasterizer::shading::ShadingModel;
+    use crate::renderer::{Framebuffer, Renderer};
+    use crate::scene::camera::{Camera, Projection, DEFAULT_FOV};
+    use crate::scene::examples::Example;
+    use crate::scene::light::{Light, Scatter};
+    use crate::scene::material::Material;
+    use crate::scene::node::{Node, Shape};
+    use crate::scene::Scene;
+    use crate::vec3::{Color, Mat3, Vec3};
 
     #[test]
-    fn test_rasterizer() {
-        let _res = rasterizer(600, 600);
+    fn test_statistics() {
+        let mut framebuffer = Framebuffer::new(60, 80);
+        let statistics = Rasterizer::default().render(&Example::Crates.scene(), &mut framebuffer);
+
+        // Every crate shows at least one face, of two triangles, and backface culling drops about half of its twelve.
+        assert_eq!(statistics.objects, 3);
+        assert!(statistics.triangles >= 3 * 2 && statistics.triangles < 3 * 12);
+        assert_eq!(statistics.rays, 0);
+    }
+
+    #[test]
+    fn test_resize() {
+        let scene = Example::Crates.scene();
+        let mut rasterizer = Rasterizer::default();
+        let mut small = Framebuffer::new(30, 40);
+        let mut large = Framebuffer::new(60, 80);
+        rasterizer.render(&scene, &mut large);
+        rasterizer.render(&scene, &mut small);
+        let first = large.colors.clone();
+
+        // The canvas is rebuilt whenever the size changes, without anything left over from the other size.
+        rasterizer.render(&scene, &mut large);
+        assert_eq!(large.colors, first);
+    }
+
+    #[test]
+    fn test_rerender_changed_material() {
+        let mut scene = Example::Spheres.scene();
+        let mut rasterizer = Rasterizer::default();
+        let mut framebuffer = Framebuffer::new(30, 40);
+        rasterizer.render(&scene, &mut framebuffer);
+        let first = framebuffer.colors.clone();
+
+        // The models are kept between frames, but built again once their materials change.
+        let color = scene.materials()[0].color;
+        scene.materials_mut()[0].color = Color::new(0., 0., 255.);
+        rasterizer.render(&scene, &mut framebuffer);
+        assert_ne!(framebuffer.colors, first);
+        scene.materials_mut()[0].color = color;
+        rasterizer.render(&scene, &mut framebuffer);
+        assert_eq!(framebuffer.colors, first);
+    }
+
+    /// A smooth, shiny sphere lit from the front left, where the shading models differ the most.
+    fn sphere_scene() -> Scene {
+        Scene::new(
+            Camera::new(
+                Vec3::new(0., 0., 0.),
+                Mat3::identity(),
+                Projection::Perspective { fov: DEFAULT_FOV },
+                1.,
+                100.,
+            ),
+            vec![
+                Light::Ambient {
+                    intensity: Color::new(0.2, 0.2, 0.2),
+                },
+                Light::Point {
+                    intensity: Color::new(0.8, 0.8, 0.8),
+                    position: Vec3::new(-2., 2., 0.),
+                },
+            ],
+            vec![Material::new(
+                Color::new(200., 60., 60.),
+                Scatter::Specular { shininess: 20. },
+                0.,
+            )],
+            vec![],
+            vec![Node::new(
+                Shape::Sphere,
+                0,
+                Vec3::new(1., 1., 1.),
+                Mat3::identity(),
+                Vec3::new(0., 0., 3.),
+            )],
+            vec![],
+        )
+    }
+
+    /// A red sphere close to the camera and a green one of the same size much further away, lit evenly.
+    fn two_spheres_scene(projection: Projection) -> Scene {
+        let sphere = |material, translation| {
+            Node::new(
+                Shape::Sphere,
+                material,
+                Vec3::new(0.5, 0.5, 0.5),
+                Mat3::identity(),
+                translation,
+            )
+        };
+        Scene::new(
+            Camera::new(
+                Vec3::new(0., 0., 0.),
+                Mat3::identity(),
+                projection,
+                0.1,
+                100.,
+            ),
+            vec![Light::Ambient {
+                intensity: Color::new(1., 1., 1.),
+            }],
+            vec![
+                Material::new(Color::new(255., 0., 0.), Scatter::Diffuse, 0.),
+                Material::new(Color::new(0., 255., 0.), Scatter::Diffuse, 0.),
+            ],
+            vec![],
+            vec![
+                sphere(0, Vec3::new(-1., 0., 3.)),
+                sphere(1, Vec3::new(1., 0., 10.)),
+            ],
+            vec![],
+        )
+    }
+
+    /// The number of mostly red and of mostly green pixels.
+    fn count_red_and_green(colors: &[f32]) -> (usize, usize) {
+        let count = |channel: usize| {
+            colors
+                .chunks(4)
+                .filter(|pixel| pixel[channel] > 0.5 && pixel[1 - channel] < 0.5)
+                .count()
+        };
+        (count(0), count(1))
+    }
+
+    #[test]
+    fn test_orthographic() {
+        let mut framebuffer = Framebuffer::new(40, 80);
+
+        // Seen in perspective, the further sphere looks much smaller.
+        let scene = two_spheres_scene(Projection::Perspective { fov: DEFAULT_FOV });
+        Rasterizer::default().render(&scene, &mut framebuffer);
+        let (near, far) = count_red_and_green(&framebuffer.colors);
+        assert!(far * 4 < near, "{} {}", near, far);
+
+        // Seen orthographically, both spheres look the same size, a quarter of the view's height across.
+        let scene = two_spheres_scene(Projection::Orthographic { height: 4. });
+        Rasterizer::default().render(&scene, &mut framebuffer);
+        let (near, far) = count_red_and_green(&framebuffer.colors);
+        let expected = std::f64::consts::PI * 5. * 5.;
+        for count in [near, far] {
+            assert!(
+                (count as f64 - expected).abs() < expected * 0.15,
+                "{}",
+                count
+            );
+        }
+        assert!(near.abs_diff(far) <= 4, "{} {}", near, far);
+    }
+
+    #[test]
+    fn test_golden_images() {
+        let mut framebuffer = Framebuffer::new(120, 160);
+        Rasterizer::default().render(&Example::Crates.scene(), &mut framebuffer);
+        framebuffer.tone_map(ToneMapping::default(), 1.);
+        assert_matches_golden("rasterizer-crates", &framebuffer.pixels, 160, 120);
+
+        Rasterizer::default().render(&Example::Cobblestones.scene(), &mut framebuffer);
+        framebuffer.tone_map(ToneMapping::default(), 1.);
+        assert_matches_golden("rasterizer-cobblestones", &framebuffer.pixels, 160, 120);
+
+        let cases = [
+            ("flat", ShadingModel::Flat, Rasterization::EdgeFunction),
+            (
+                "gouraud",
+                ShadingModel::Gouraud,
+                Rasterization::EdgeFunction,
+            ),
+            ("phong", ShadingModel::Phong, Rasterization::EdgeFunction),
+            (
+                "phong-scanline",
+                ShadingModel::Phong,
+                Rasterization::Scanline,
+            ),
+        ];
+        for (name, shading_model, rasterization) in cases {
+            let mut rasterizer = Rasterizer::new(shading_model, rasterization, Multisampling::X4);
+            rasterizer.render(&sphere_scene(), &mut framebuffer);
+            framebuffer.tone_map(ToneMapping::default(), 1.);
+            assert_matches_golden(
+                &format!("rasterizer-sphere-{}", name),
+                &framebuffer.pixels,
+                160,
+                120,
+            );
+        }
     }
 }
@@ -1,19 +1,26 @@
-use crate::rasterizer::light::Scatter;
-use crate::rasterizer::surface::Surface;
+use crate::rasterizer::surface::{NormalMap, Surface};
 use crate::rasterizer::triangle::Triangle;
+use crate::scene::material::{HeightMap, Material};
+use crate::scene::mesh::{self, Mesh, TangentFrame};
+use crate::scene::node::Shape;
+use crate::scene::texture::Texture;
 use crate::vec3::Vec3;
 use std::f64::consts::PI;
 
+// The number of slices, and of stacks, that spheres are tessellated into.
+const SPHERE_DIVISIONS: usize = 24;
+
 pub struct Model {
     pub vertices: Vec<Vec3>,
     pub triangles: Vec<Triangle>,
-    pub scatter: Scatter,
+    // How the model's surfaces are lit. Their colors are kept by each triangle's surface.
+    pub material: Material,
     pub bounds_center: Vec3,
     pub bounds_radius: f64,
 }
 
 impl Model {
-    pub fn new(vertices: Vec<Vec3>, triangles: Vec<Triangle>, scatter: Scatter) -> Model {
+    pub fn new(vertices: Vec<Vec3>, triangles: Vec<Triangle>, material: Material) -> Model {
         let bounds_center = vertices
             .iter()
             .fold(Vec3::new(0., 0., 0.), |v1, v2| v1 + v2)
@@ -27,13 +34,143 @@ impl Model {
         Model {
             vertices,
             triangles,
-            scatter,
+            material,
             bounds_center,
             bounds_radius,
         }
     }
 
-    pub fn make_sphere(divs: usize, surface: Surface, scatter: Scatter) -> Model {
+    /// Creates the model that renders a shape of the scene, made of the given material. Meshes are displaced by the
+    /// material's height map when it asks for it.
+    pub fn from_shape(
+        shape: &Shape,
+        meshes: &[Mesh],
+        material: &Material,
+        textures: &[Texture],
+    ) -> Model {
+        match shape {
+            Shape::Mesh(mesh) => {
+                let mesh = &meshes[*mesh];
+                let frames = material.bump().map(|_| mesh.tangent_frames());
+                let triangles = mesh
+                    .faces
+                    .iter()
+                    .enumerate()
+                    .map(|(f, face)| Triangle {
+                        vertex_indices: face.vertices,
+                        surface: match material.texture {
+                            Some(index) => Surface::Texture {
+                                index,
+                                uvs: face.uvs,
+                            },
+                            None => Surface::Color(material.color),
+                        },
+                        normals: face.normals,
+                        normal_map: frames.as_ref().zip(material.bump()).map(
+                            |(frames, (index, depth))| NormalMap {
+                                index,
+                                depth,
+                                uvs: face.uvs,
+                                frames: frames[f],
+                            },
+                        ),
+                    })
+                    .collect();
+                let model = Model::new(mesh.vertices.clone(), triangles, *material);
+                match material.height_map {
+                    Some(height_map) if height_map.divisions > 1 => {
+                        model.displaced(&height_map, textures)
+                    }
+                    _ => model,
+                }
+            }
+            Shape::Sphere => Model::make_sphere(SPHERE_DIVISIONS, *material),
+        }
+    }
+
+    /// Splits each triangle into smaller ones, as many to a side as the height map's divisions, and moves their
+    /// vertices out along the surface's normals by the height map at their texture coordinates. Triangles without
+    /// texture coordinates are only split. Each triangle gets vertices of its own, so triangles that meet at a hard
+    /// edge can be pulled apart there.
+    pub fn displaced(&self, height_map: &HeightMap, textures: &[Texture]) -> Model {
+        let n = height_map.divisions;
+        let texture = &textures[height_map.texture];
+        let weights = |i: usize, j: usize| {
+            let (a, b) = (i as f64 / n as f64, j as f64 / n as f64);
+            [1. - a - b, a, b]
+        };
+
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for triangle in self.triangles.iter() {
+            let corners = triangle.vertex_indices.map(|i| &self.vertices[i]);
+            let normals = triangle
+                .normals
+                .unwrap_or([triangle.normal(&self.vertices); 3]);
+            // The height map's depth is a fraction of its width, which is as long as u going from 0 to 1.
+            let uvs_and_scale = triangle.normal_map.as_ref().map(|normal_map| {
+                let frame = TangentFrame::of_triangle(corners, normal_map.uvs);
+                let scale = frame.map_or(0., |frame| frame.tangent.len());
+                (normal_map.uvs, scale * height_map.depth)
+            });
+
+            // The new vertices are in rows from the first corner to the second, each of them running towards the
+            // third.
+            let mut grid = Vec::new();
+            for i in 0..=n {
+                let mut row = Vec::new();
+                for j in 0..=(n - i) {
+                    let w = weights(i, j);
+                    let mut vertex = corners[0] * w[0] + corners[1] * w[1] + corners[2] * w[2];
+                    if let Some((uvs, scale)) = uvs_and_scale {
+                        let u = uvs[0].0 * w[0] + uvs[1].0 * w[1] + uvs[2].0 * w[2];
+                        let v = uvs[0].1 * w[0] + uvs[1].1 * w[1] + uvs[2].1 * w[2];
+                        let normal = normals[0] * w[0] + normals[1] * w[1] + normals[2] * w[2];
+                        vertex =
+                            vertex + normal * (texture.texel_height(u, v) * scale / normal.len());
+                    }
+                    row.push(vertices.len());
+                    vertices.push(vertex);
+                }
+                grid.push(row);
+            }
+
+            for i in 0..n {
+                for j in 0..(n - i) {
+                    triangles.push(triangle.clipped(
+                        [grid[i][j], grid[i + 1][j], grid[i][j + 1]],
+                        [weights(i, j), weights(i + 1, j), weights(i, j + 1)],
+                    ));
+                    if i + j + 1 < n {
+                        triangles.push(triangle.clipped(
+                            [grid[i + 1][j], grid[i + 1][j + 1], grid[i][j + 1]],
+                            [weights(i + 1, j), weights(i + 1, j + 1), weights(i, j + 1)],
+                        ));
+                    }
+                }
+            }
+        }
+        Model::new(vertices, triangles, self.material)
+    }
+
+    /// Replaces the normals of the triangles with smooth ones, so models without normals of their own, such as
+    /// procedural ones, shade smoothly. Edges sharper than `crease_angle` degrees stay hard; see
+    /// `mesh::smooth_normals`.
+    pub fn with_smooth_normals(mut self, crease_angle: f64) -> Model {
+        let normals = mesh::smooth_normals(
+            &self.vertices,
+            &self.triangles,
+            |triangle| triangle.vertex_indices,
+            crease_angle,
+        );
+        for (triangle, normals) in self.triangles.iter_mut().zip(normals) {
+            triangle.normals = Some(normals);
+        }
+        self
+    }
+
+    pub fn make_sphere(divs: usize, material: Material) -> Model {
+        let surface = Surface::Color(material.color);
         let mut vertices = Vec::new();
         let mut triangles = Vec::new();
         let n_divs = divs as f64;
@@ -54,33 +191,81 @@ impl Model {
                 let i0 = d * divs + i;
                 let i1 = (d + 1) * divs + (i + 1) % divs;
                 let i2 = divs * d + (i + 1) % divs;
-                triangles.push(Triangle::new(
-                    i0,
-                    i1,
-                    i2,
-                    surface.clone(),
-                    vertices[i0],
-                    vertices[i1],
-                    vertices[i2],
-                ));
-                triangles.push(Triangle::new(
-                    i0,
-                    i0 + divs,
-                    i1,
-                    surface.clone(),
-                    vertices[i0],
-                    vertices[i0 + divs],
-                    vertices[i1],
-                ));
+                triangles.push(Triangle::new_no_normals(i0, i1, i2, surface.clone()));
+                triangles.push(Triangle::new_no_normals(i0, i0 + divs, i1, surface.clone()));
             }
         }
 
         Model {
             vertices,
             triangles,
-            scatter,
+            material,
             bounds_center: Vec3::new(0., 0., 0.),
             bounds_radius: 1.0,
         }
+        // The sphere has no edges.
+        .with_smooth_normals(180.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rasterizer::model::Model;
+    use crate::scene::light::Scatter;
+    use crate::scene::material::{HeightMap, Material};
+    use crate::scene::mesh::{Face, Mesh};
+    use crate::scene::node::Shape;
+    use crate::scene::texture::Texture;
+    use crate::vec3::{Color, Vec3};
+
+    #[test]
+    fn test_displaced() {
+        // A square facing up, two units wide, with a height map that rises from black on the right to white on the
+        // left.
+        let up = Vec3::new(0., 1., 0.);
+        let square = Mesh::new(
+            vec![
+                Vec3::new(-1., 0., 1.),
+                Vec3::new(1., 0., 1.),
+                Vec3::new(1., 0., -1.),
+                Vec3::new(-1., 0., -1.),
+            ],
+            vec![
+                Face::new([0, 1, 2], Some([up; 3]), [(0., 0.), (1., 0.), (1., 1.)]),
+                Face::new([0, 2, 3], Some([up; 3]), [(0., 0.), (1., 1.), (0., 1.)]),
+            ],
+        );
+        let height_map = Texture::from_fn(2, 2, |u, _| {
+            let height = (1. - u) * 255.;
+            Color::new(height, height, height)
+        });
+        let material = Material::new(Color::new(255., 255., 255.), Scatter::Diffuse, 0.)
+            .with_height_map(HeightMap::new(0, 0.1).with_displacement(4));
+
+        let model = Model::from_shape(&Shape::Mesh(0), &[square], &material, &[height_map]);
+        assert_eq!(model.triangles.len(), 2 * 4 * 4);
+        for v in model.vertices.iter() {
+            // White stands a tenth of the texture's width over the surface.
+            let u = (v[0] + 1.) / 2.;
+            assert!((v[1] - (1. - u) * 0.2).abs() < 1e-9, "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn test_sphere_normals() {
+        // The smooth normals of a sphere point within a few degrees of straight out from its center, even at the poles,
+        // where its vertices meet.
+        let material = Material::new(Color::new(255., 255., 255.), Scatter::Diffuse, 0.);
+        let sphere = Model::make_sphere(24, material);
+        for triangle in sphere.triangles.iter() {
+            for (n, &v) in triangle
+                .normals
+                .unwrap()
+                .iter()
+                .zip(&triangle.vertex_indices)
+            {
+                assert!(n.dot(&sphere.vertices[v]) > 0.99, "{:?}", n);
+            }
+        }
     }
 }
@@ -10,9 +10,10 @@ impl Plane {
         Plane { normal, distance }
     }
 
-    pub fn segment_intersection(&self, a: &Vec3, b: &Vec3) -> Vec3 {
+    /// Returns the point at which the segment from a to b crosses the plane, and how far along the segment it is.
+    pub fn segment_intersection(&self, a: &Vec3, b: &Vec3) -> (Vec3, f64) {
         let b_minus_a = b - a;
-        let t = -self.distance - self.normal.dot(a) / self.normal.dot(&b_minus_a);
-        a + b_minus_a * t
+        let t = (-self.distance - self.normal.dot(a)) / self.normal.dot(&b_minus_a);
+        (a + b_minus_a * t, t)
     }
 }
@@ -10,3 +10,16 @@ impl Point {
         Point { x, y, h }
     }
 }
+
+/// A projected vertex in fixed-point canvas coordinates, with `rasterization::SUBPIXEL_BITS` bits of fraction.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SubpixelPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl SubpixelPoint {
+    pub fn new(x: i64, y: i64) -> SubpixelPoint {
+        SubpixelPoint { x, y }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::rasterizer::point::SubpixelPoint;
+
+pub const SUBPIXEL_BITS: u32 = 8;
+pub const SUBPIXEL_SCALE: i64 = 1 << SUBPIXEL_BITS;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Rasterization {
+    // Walks the triangle a horizontal line at a time, interpolating along its edges. Vertices are truncated to whole
+    // pixels, so adjacent triangles can leave cracks or draw the same pixel twice.
+    Scanline,
+
+    // Tests every pixel in the triangle's bounding box against its three edge functions. Vertices keep
+    // `SUBPIXEL_BITS` of precision and shared edges follow the top-left fill rule, so meshes are watertight.
+    EdgeFunction,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Multisampling {
+    // One sample at the center of each pixel.
+    Off,
+
+    // Coverage and depth are tested at several points within each pixel, but the pixel is only shaded once. The
+    // scanline rasterizer has no notion of partial coverage, so it treats every sample of the pixels it draws as
+    // covered.
+    X2,
+    X4,
+    X8,
+}
+
+// The standard sample patterns, in sixteenths of a pixel from the pixel's center.
+const SAMPLES_1X: [(i64, i64); 1] = [(0, 0)];
+const SAMPLES_2X: [(i64, i64); 2] = [(4, 4), (-4, -4)];
+const SAMPLES_4X: [(i64, i64); 4] = [(-2, -6), (6, -2), (-6, 2), (2, 6)];
+const SAMPLES_8X: [(i64, i64); 8] = [
+    (1, -3),
+    (-1, 3),
+    (5, 1),
+    (-3, -5),
+    (-5, 5),
+    (-7, -1),
+    (3, 7),
+    (7, -7),
+];
+
+impl Multisampling {
+    pub fn samples(&self) -> usize {
+        self.sample_offsets().len()
+    }
+
+    fn sample_offsets(&self) -> &'static [(i64, i64)] {
+        match self {
+            Multisampling::Off => &SAMPLES_1X,
+            Multisampling::X2 => &SAMPLES_2X,
+            Multisampling::X4 => &SAMPLES_4X,
+            Multisampling::X8 => &SAMPLES_8X,
+        }
+    }
+}
+
+/// Visits every pixel with at least one sample inside the triangle.
+///
+/// # Arguments
+///
+/// * `points`: the projected vertices, in any winding order.
+/// * `x_range`, `y_range`: the inclusive range of pixels that can be drawn, in canvas coordinates.
+/// * `multisampling`: where in each pixel to sample coverage.
+/// * `f`: called with the pixel's x and y, the barycentric weights of the triangle's vertices at the pixel's center,
+///   and, for each sample, its barycentric weights if it's covered.
+///
+/// A sample that falls exactly on an edge is only covered if that is a top or left edge, so a sample on the edge
+/// shared by two triangles is covered by exactly one of them.
+///
+pub fn rasterize_edge_function<F>(
+    points: [&SubpixelPoint; 3],
+    x_range: (i64, i64),
+    y_range: (i64, i64),
+    multisampling: Multisampling,
+    mut f: F,
+) where
+    F: FnMut(i64, i64, [f64; 3], &[Option<[f64; 3]>]),
+{
+    // Work with counter-clockwise triangles, remembering where each vertex came from.
+    let mut order = [0, 1, 2];
+    let mut p = points;
+    let mut area = edge(p[0], p[1], p[2].x, p[2].y);
+    if area == 0 {
+        return;
+    } else if area < 0 {
+        p.swap(1, 2);
+        order.swap(1, 2);
+        area = -area;
+    }
+    let weights = |w: [i64; 3]| {
+        let mut weights = [0.; 3];
+        for i in 0..3 {
+            weights[order[i]] = w[i] as f64 / area as f64;
+        }
+        weights
+    };
+
+    // The bounding box, in whole pixels.
+    let xs = p.map(|p| p.x.div_euclid(SUBPIXEL_SCALE));
+    let ys = p.map(|p| p.y.div_euclid(SUBPIXEL_SCALE));
+    let x_min = xs.iter().min().unwrap().max(&x_range.0).to_owned();
+    let x_max = xs.iter().max().unwrap().min(&x_range.1).to_owned();
+    let y_min = ys.iter().min().unwrap().max(&y_range.0).to_owned();
+    let y_max = ys.iter().max().unwrap().min(&y_range.1).to_owned();
+    if x_min > x_max || y_min > y_max {
+        return;
+    }
+
+    // The weight of each vertex is the edge function of the opposite edge.
+    let edges = [(p[1], p[2]), (p[2], p[0]), (p[0], p[1])];
+    let bias = edges.map(|(a, b)| if is_top_left(a, b) { 0 } else { -1 });
+    let dx = edges.map(|(a, b)| a.y - b.y);
+    let dy = edges.map(|(a, b)| b.x - a.x);
+
+    // How far each sample moves the edge functions from their value at the pixel's center.
+    let offsets = multisampling.sample_offsets();
+    let mut sample_steps = [[0; 3]; SAMPLES_8X.len()];
+    for (s, &(ox, oy)) in offsets.iter().enumerate() {
+        let (ox, oy) = (ox * SUBPIXEL_SCALE / 16, oy * SUBPIXEL_SCALE / 16);
+        sample_steps[s] = [0, 1, 2].map(|i| dx[i] * ox + dy[i] * oy);
+    }
+    let sample_steps = &sample_steps[..offsets.len()];
+
+    let half = SUBPIXEL_SCALE / 2;
+    let (x0, y0) = (x_min * SUBPIXEL_SCALE + half, y_min * SUBPIXEL_SCALE + half);
+    let mut row = edges.map(|(a, b)| edge(a, b, x0, y0));
+    let mut samples = [None; SAMPLES_8X.len()];
+
+    for y in y_min..(y_max + 1) {
+        let mut w = row;
+        for x in x_min..(x_max + 1) {
+            let mut covered = false;
+            for (s, step) in sample_steps.iter().enumerate() {
+                let ws = [0, 1, 2].map(|i| w[i] + step[i]);
+                samples[s] = if (0..3).all(|i| ws[i] + bias[i] >= 0) {
+                    covered = true;
+                    Some(weights(ws))
+                } else {
+                    None
+                };
+            }
+            if covered {
+                f(x, y, weights(w), &samples[..offsets.len()]);
+            }
+            for i in 0..3 {
+                w[i] += dx[i] * SUBPIXEL_SCALE;
+            }
+        }
+        for i in 0..3 {
+            row[i] += dy[i] * SUBPIXEL_SCALE;
+        }
+    }
+}
+
+/// Twice the signed area of the triangle (a, b, p); positive when p is to the left of the edge from a to b.
+fn edge(a: &SubpixelPoint, b: &SubpixelPoint, px: i64, py: i64) -> i64 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+/// With y pointing up and counter-clockwise winding, a top edge is horizontal and runs right to left and a left edge
+/// runs downwards.
+fn is_top_left(a: &SubpixelPoint, b: &SubpixelPoint) -> bool {
+    (a.y == b.y && b.x < a.x) || b.y < a.y
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rasterizer::point::SubpixelPoint;
+    use crate::rasterizer::rasterization::{
+        rasterize_edge_function, Multisampling, SUBPIXEL_SCALE,
+    };
+
+    fn coverage(triangles: &[[SubpixelPoint; 3]], multisampling: Multisampling) -> Vec<u32> {
+        let n = multisampling.samples();
+        let mut counts = vec![0; 32 * 32 * n];
+        for t in triangles {
+            rasterize_edge_function(
+                [&t[0], &t[1], &t[2]],
+                (-16, 15),
+                (-16, 15),
+                multisampling,
+                |x, y, _w, samples| {
+                    for (s, sample) in samples.iter().enumerate() {
+                        if sample.is_some() {
+                            counts[((y + 16) * 32 + x + 16) as usize * n + s] += 1;
+                        }
+                    }
+                },
+            );
+        }
+        counts
+    }
+
+    #[test]
+    fn test_shared_edges_are_drawn_once() {
+        // Offsets of 0 put the square's outer edges on pixel boundaries, a half pixel puts them through pixel centers.
+        // Either way the diagonal passes through pixel centers.
+        for offset in [0, SUBPIXEL_SCALE / 2] {
+            let p = |x: i64, y: i64| {
+                SubpixelPoint::new(x * SUBPIXEL_SCALE + offset, y * SUBPIXEL_SCALE + offset)
+            };
+            let (a, b, c, d) = (p(-4, -4), p(4, -4), p(4, 4), p(-4, 4));
+            for multisampling in [
+                Multisampling::Off,
+                Multisampling::X2,
+                Multisampling::X4,
+                Multisampling::X8,
+            ] {
+                let counts = coverage(&[[a, b, c], [a, c, d]], multisampling);
+                assert!(counts.iter().all(|&c| c <= 1));
+                assert_eq!(
+                    counts.iter().sum::<u32>(),
+                    64 * multisampling.samples() as u32
+                );
+
+                // Winding order doesn't matter.
+                assert_eq!(coverage(&[[a, c, b], [d, c, a]], multisampling), counts);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weights() {
+        let s = SUBPIXEL_SCALE;
+        let triangle = [
+            SubpixelPoint::new(0, 0),
+            SubpixelPoint::new(8 * s, 0),
+            SubpixelPoint::new(0, 8 * s),
+        ];
+        rasterize_edge_function(
+            [&triangle[0], &triangle[1], &triangle[2]],
+            (-16, 15),
+            (-16, 15),
+            Multisampling::Off,
+            |x, y, w, _samples| {
+                assert!((w[0] + w[1] + w[2] - 1.).abs() < 1e-9);
+                assert!((w[1] - (x as f64 + 0.5) / 8.).abs() < 1e-9);
+                assert!((w[2] - (y as f64 + 0.5) / 8.).abs() < 1e-9);
+            },
+        );
+    }
+
+    #[test]
+    fn test_partial_coverage() {
+        // A triangle whose left edge runs through the middle of the column of pixels at x = 0.
+        let s = SUBPIXEL_SCALE;
+        let triangle = [
+            SubpixelPoint::new(s / 2, 8 * s),
+            SubpixelPoint::new(s / 2, -8 * s),
+            SubpixelPoint::new(8 * s, 0),
+        ];
+        rasterize_edge_function(
+            [&triangle[0], &triangle[1], &triangle[2]],
+            (-16, 15),
+            (-16, 15),
+            Multisampling::X4,
+            |x, y, _w, samples| {
+                let covered = samples.iter().filter(|s| s.is_some()).count();
+                if x == 0 && y.abs() < 4 {
+                    assert_eq!(covered, 2);
+                }
+            },
+        );
+    }
+}
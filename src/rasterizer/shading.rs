@@ -1,11 +1,49 @@
 use crate::rasterizer::camera::Camera;
-use crate::rasterizer::light::{Light, Scatter};
 use crate::rasterizer::point::Point;
+use crate::rasterizer::surface::NormalMap;
 use crate::rasterizer::util;
+use crate::scene::light::{Light, Reflection, Scatter};
+use crate::scene::material::Material;
+use crate::scene::texture::Texture;
 use crate::vec3::Vec3;
 use itertools::Itertools;
 use std::iter;
 
+/// The light reflected towards the camera from a vertex, with the vertex, normal and lights in camera space. The
+/// rasterizer doesn't cast shadows.
+fn reflected_intensity(
+    scatter: &Scatter,
+    vertex: &Vec3,
+    normal: &Vec3,
+    lights: &[Light],
+) -> Reflection {
+    scatter.intensity(lights, vertex, normal, &-vertex, |_, _| false)
+}
+
+/// What shading a triangle takes besides the triangle itself: the camera and the lights, which are in its space, and
+/// the material and textures the triangle is made of.
+pub struct ShadingContext<'a> {
+    pub camera: &'a Camera,
+    pub lights: &'a [Light],
+    pub material: &'a Material,
+    pub textures: &'a [Texture],
+}
+
+/// A row of pixels across a triangle, as the scanline rasterizer walks it between the triangle's two edges.
+pub struct Scanline<'a> {
+    // Which of the edges is on the left, and which is on the right.
+    pub left: usize,
+    pub right: usize,
+    // How far along the edges the row is, and its y.
+    pub i: usize,
+    pub y: i64,
+    // Where the row meets each edge.
+    pub x: [f64; 2],
+    // 1/z at each pixel of the row, from left to right.
+    pub inv_z: &'a [f64],
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ShadingModel {
     Flat,
     Gouraud,
@@ -13,25 +51,27 @@ pub enum ShadingModel {
 }
 
 impl ShadingModel {
+    /// Prepares to shade a triangle along its edges. Only Phong shading reads the normal map, which has to have its
+    /// corners in the same order as the vertices.
     pub fn shader(
         &self,
         vertices: [&Vec3; 3],
         points: [&Point; 3],
         normals: [&Vec3; 3],
-        camera: &Camera,
-        lights: &Vec<Light>,
-        scatter: &Scatter,
+        normal_map: Option<&NormalMap>,
+        context: &ShadingContext,
     ) -> Shader {
+        let (scatter, lights) = (&context.material.scatter, context.lights);
         match self {
             ShadingModel::Flat => {
                 let center = (vertices[0] + vertices[1] + vertices[2]) / 3.0;
-                let intensity = scatter.intensity(&center, &normals[0], camera, lights);
-                Shader::Flat { intensity }
+                let reflection = reflected_intensity(scatter, &center, normals[0], lights);
+                Shader::Flat { reflection }
             }
             ShadingModel::Gouraud => {
-                let i0 = scatter.intensity(&vertices[0], &normals[0], camera, lights);
-                let i1 = scatter.intensity(&vertices[1], &normals[1], camera, lights);
-                let i2 = scatter.intensity(&vertices[2], &normals[2], camera, lights);
+                let i0 = reflected_intensity(scatter, vertices[0], normals[0], lights);
+                let i1 = reflected_intensity(scatter, vertices[1], normals[1], lights);
+                let i2 = reflected_intensity(scatter, vertices[2], normals[2], lights);
                 let i_edges =
                     util::edge_interpolate(points[0].y, i0, points[1].y, i1, points[2].y, i2);
                 Shader::Gouraud { i_edges }
@@ -65,45 +105,85 @@ impl ShadingModel {
                     nx_edges,
                     ny_edges,
                     nz_edges,
+                    vertices: [*vertices[0], *vertices[1], *vertices[2]],
+                    normal_map: normal_map.cloned(),
                 }
             }
         }
     }
+
+    /// Like `shader`, but for rasterizers that evaluate each pixel from its barycentric weights rather than walking
+    /// the triangle's edges.
+    pub fn barycentric_shader(
+        &self,
+        vertices: [&Vec3; 3],
+        normals: [&Vec3; 3],
+        normal_map: Option<&NormalMap>,
+        context: &ShadingContext,
+    ) -> BarycentricShader {
+        let (scatter, lights) = (&context.material.scatter, context.lights);
+        match self {
+            ShadingModel::Flat => {
+                let center = (vertices[0] + vertices[1] + vertices[2]) / 3.0;
+                let reflection = reflected_intensity(scatter, &center, normals[0], lights);
+                BarycentricShader::Flat { reflection }
+            }
+            ShadingModel::Gouraud => BarycentricShader::Gouraud {
+                reflections: [
+                    reflected_intensity(scatter, vertices[0], normals[0], lights),
+                    reflected_intensity(scatter, vertices[1], normals[1], lights),
+                    reflected_intensity(scatter, vertices[2], normals[2], lights),
+                ],
+            },
+            ShadingModel::Phong => BarycentricShader::Phong {
+                vertices: [*vertices[0], *vertices[1], *vertices[2]],
+                normals: [*normals[0], *normals[1], *normals[2]],
+                normal_map: normal_map.cloned(),
+            },
+        }
+    }
 }
 
 pub enum Shader {
     Flat {
-        intensity: f64,
+        reflection: Reflection,
     },
     Gouraud {
-        i_edges: [Vec<(i64, f64)>; 2],
+        i_edges: [Vec<(i64, Reflection)>; 2],
     },
     Phong {
         nx_edges: [Vec<(i64, f64)>; 2],
         ny_edges: [Vec<(i64, f64)>; 2],
         nz_edges: [Vec<(i64, f64)>; 2],
+        // The vertices, to find where each pixel is in the triangle to read the normal map there.
+        vertices: [Vec3; 3],
+        normal_map: Option<NormalMap>,
     },
 }
 
 impl Shader {
+    /// The reflections at each pixel of the scanline, on a canvas of the given size.
     pub fn intensities(
         &self,
-        left: usize,
-        right: usize,
-        i: usize,
-        y: i64,
-        x: [f64; 2],
-        inv_z: &Vec<f64>,
+        scanline: &Scanline,
         canvas_width: i64,
         canvas_height: i64,
-        camera: &Camera,
-        lights: &Vec<Light>,
-        scatter: &Scatter,
-    ) -> Vec<f64> {
+        context: &ShadingContext,
+    ) -> Vec<Reflection> {
+        let &Scanline {
+            left,
+            right,
+            i,
+            y,
+            x,
+            inv_z,
+        } = scanline;
         match self {
-            &Shader::Flat { intensity } => iter::repeat(intensity)
-                .take((x[right].ceil() - x[left].floor() + 1.0).max(0.0) as usize)
-                .collect_vec(),
+            &Shader::Flat { reflection } => iter::repeat_n(
+                reflection,
+                (x[right].ceil() - x[left].floor() + 1.0).max(0.0) as usize,
+            )
+            .collect_vec(),
             Shader::Gouraud { i_edges } => util::interpolate(
                 x[left].floor() as i64,
                 i_edges[left][i].1,
@@ -116,29 +196,19 @@ impl Shader {
                 nx_edges,
                 ny_edges,
                 nz_edges,
+                vertices,
+                normal_map,
             } => {
                 let x_left = x[left].floor() as i64;
                 let x_right = x[right].ceil() as i64;
-                let nxscan = util::interpolate(
-                    x_left,
-                    nx_edges[left][i].1 as f64,
-                    x_right,
-                    nx_edges[right][i].1 as f64,
-                );
-                let nyscan = util::interpolate(
-                    x_left,
-                    ny_edges[left][i].1 as f64,
-                    x_right,
-                    ny_edges[right][i].1 as f64,
-                )
-                .map(|(_i, d)| d);
-                let nzscan = util::interpolate(
-                    x_left,
-                    nz_edges[left][i].1 as f64,
-                    x_right,
-                    nz_edges[right][i].1 as f64,
-                )
-                .map(|(_i, d)| d);
+                let nxscan =
+                    util::interpolate(x_left, nx_edges[left][i].1, x_right, nx_edges[right][i].1);
+                let nyscan =
+                    util::interpolate(x_left, ny_edges[left][i].1, x_right, ny_edges[right][i].1)
+                        .map(|(_i, d)| d);
+                let nzscan =
+                    util::interpolate(x_left, nz_edges[left][i].1, x_right, nz_edges[right][i].1)
+                        .map(|(_i, d)| d);
 
                 nxscan
                     .zip(nyscan)
@@ -149,14 +219,69 @@ impl Shader {
                             x as f64,
                             y as f64,
                             inv_z,
+                            &context.camera.viewport,
                             canvas_width,
                             canvas_height,
                         );
-                        let normal = Vec3::new(nx, ny, nz);
-                        scatter.intensity(&vertex, &normal, camera, lights)
+                        let mut normal = Vec3::new(nx, ny, nz);
+                        if let Some(normal_map) = normal_map {
+                            let weights = util::barycentric_weights(
+                                &vertex,
+                                [&vertices[0], &vertices[1], &vertices[2]],
+                            );
+                            normal = normal_map.normal_at(weights, &normal, context.textures);
+                        }
+                        reflected_intensity(
+                            &context.material.scatter,
+                            &vertex,
+                            &normal,
+                            context.lights,
+                        )
                     })
                     .collect_vec()
             }
         }
     }
 }
+
+pub enum BarycentricShader {
+    Flat {
+        reflection: Reflection,
+    },
+    Gouraud {
+        reflections: [Reflection; 3],
+    },
+    Phong {
+        vertices: [Vec3; 3],
+        normals: [Vec3; 3],
+        normal_map: Option<NormalMap>,
+    },
+}
+
+impl BarycentricShader {
+    /// The reflection at the point with the given perspective-correct barycentric `weights`.
+    pub fn intensity(&self, weights: [f64; 3], context: &ShadingContext) -> Reflection {
+        match self {
+            &BarycentricShader::Flat { reflection } => reflection,
+            BarycentricShader::Gouraud { reflections } => {
+                reflections[0] * weights[0]
+                    + reflections[1] * weights[1]
+                    + reflections[2] * weights[2]
+            }
+            BarycentricShader::Phong {
+                vertices,
+                normals,
+                normal_map,
+            } => {
+                let vertex =
+                    vertices[0] * weights[0] + vertices[1] * weights[1] + vertices[2] * weights[2];
+                let mut normal =
+                    normals[0] * weights[0] + normals[1] * weights[1] + normals[2] * weights[2];
+                if let Some(normal_map) = normal_map {
+                    normal = normal_map.normal_at(weights, &normal, context.textures);
+                }
+                reflected_intensity(&context.material.scatter, &vertex, &normal, context.lights)
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::rasterizer::point::Point;
-use crate::rasterizer::texture::Texture;
 use crate::rasterizer::util;
-use crate::vec3::{Color, Vec3};
+use crate::scene::mesh::TangentFrame;
+use crate::scene::texture::Texture;
+use crate::vec3::{Color, Mat3, Vec3};
 use itertools::Itertools;
 use std::iter;
 
@@ -15,36 +16,121 @@ impl Surface {
     pub fn color_generator(
         &self,
         indexes: [usize; 3],
-        vertices: [&Vec3; 3],
+        perspective_weights: [f64; 3],
         points: [&Point; 3],
     ) -> ColorGenerator {
+        let w = perspective_weights;
         match &self {
             Surface::Texture { index, uvs } => {
                 let uz = util::edge_interpolate(
                     points[0].y,
-                    uvs[indexes[0]].0 as f64 / vertices[0][2],
+                    uvs[indexes[0]].0 * w[0],
                     points[1].y,
-                    uvs[indexes[1]].0 as f64 / vertices[1][2],
+                    uvs[indexes[1]].0 * w[1],
                     points[2].y,
-                    uvs[indexes[2]].0 as f64 / vertices[2][2],
+                    uvs[indexes[2]].0 * w[2],
                 );
                 let vz = util::edge_interpolate(
                     points[0].y,
-                    uvs[indexes[0]].1 as f64 / vertices[0][2],
+                    uvs[indexes[0]].1 * w[0],
                     points[1].y,
-                    uvs[indexes[1]].1 as f64 / vertices[1][2],
+                    uvs[indexes[1]].1 * w[1],
                     points[2].y,
-                    uvs[indexes[2]].1 as f64 / vertices[2][2],
+                    uvs[indexes[2]].1 * w[2],
                 );
+                let wz =
+                    util::edge_interpolate(points[0].y, w[0], points[1].y, w[1], points[2].y, w[2]);
                 ColorGenerator::Texture {
                     index: *index,
                     uz,
                     vz,
+                    wz,
                 }
             }
             Surface::Color(color) => ColorGenerator::Color(*color),
         }
     }
+
+    /// The surface of part of a triangle whose vertices have the given weights in the original triangle.
+    pub fn clipped(&self, weights: [[f64; 3]; 3]) -> Surface {
+        match &self {
+            Surface::Texture { index, uvs } => Surface::Texture {
+                index: *index,
+                uvs: weights.map(|w| {
+                    (
+                        uvs[0].0 * w[0] + uvs[1].0 * w[1] + uvs[2].0 * w[2],
+                        uvs[0].1 * w[0] + uvs[1].1 * w[1] + uvs[2].1 * w[2],
+                    )
+                }),
+            },
+            Surface::Color(color) => Surface::Color(*color),
+        }
+    }
+
+    /// The color at the point with the given perspective-correct barycentric `weights`.
+    pub fn color_at(&self, weights: [f64; 3], textures: &[Texture]) -> Color {
+        match &self {
+            Surface::Texture { index, uvs } => {
+                let u = uvs[0].0 * weights[0] + uvs[1].0 * weights[1] + uvs[2].0 * weights[2];
+                let v = uvs[0].1 * weights[0] + uvs[1].1 * weights[1] + uvs[2].1 * weights[2];
+                textures[*index].texel_color(u, v)
+            }
+            Surface::Color(color) => *color,
+        }
+    }
+}
+
+/// The normal map a triangle is shaded with, and where the triangle's corners are on it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NormalMap {
+    // The index of the normal map in the scene's textures.
+    pub index: usize,
+    // When the texture is a height map instead, how high its white stands over the surface, as a fraction of its
+    // width.
+    pub depth: Option<f64>,
+    pub uvs: [(f64, f64); 3],
+    pub frames: [TangentFrame; 3],
+}
+
+impl NormalMap {
+    /// The normal map of part of a triangle whose vertices have the given weights in the original triangle.
+    pub fn clipped(&self, weights: [[f64; 3]; 3]) -> NormalMap {
+        NormalMap {
+            index: self.index,
+            depth: self.depth,
+            uvs: weights.map(|w| {
+                (
+                    self.uvs[0].0 * w[0] + self.uvs[1].0 * w[1] + self.uvs[2].0 * w[2],
+                    self.uvs[0].1 * w[0] + self.uvs[1].1 * w[1] + self.uvs[2].1 * w[2],
+                )
+            }),
+            frames: weights.map(|w| TangentFrame::interpolate(&self.frames, w)),
+        }
+    }
+
+    /// The normal map with its frames moved into another space, such as the camera's.
+    pub fn transformed(&self, transformation: &Mat3) -> NormalMap {
+        NormalMap {
+            index: self.index,
+            depth: self.depth,
+            uvs: self.uvs,
+            frames: self.frames.map(|frame| frame.transformed(transformation)),
+        }
+    }
+
+    /// The `normal` interpolated at the point with the given perspective-correct barycentric `weights`, tilted by the
+    /// normal map there.
+    pub fn normal_at(&self, weights: [f64; 3], normal: &Vec3, textures: &[Texture]) -> Vec3 {
+        let uvs = &self.uvs;
+        let u = uvs[0].0 * weights[0] + uvs[1].0 * weights[1] + uvs[2].0 * weights[2];
+        let v = uvs[0].1 * weights[0] + uvs[1].1 * weights[1] + uvs[2].1 * weights[2];
+        let (u, v) = (u.clamp(0., 1.), v.clamp(0., 1.));
+        let mapped = match self.depth {
+            Some(depth) => textures[self.index].height_normal(u, v, depth),
+            None => textures[self.index].texel_normal(u, v),
+        };
+        TangentFrame::interpolate(&self.frames, weights).perturb(normal, &mapped)
+    }
 }
 
 pub enum ColorGenerator {
@@ -52,6 +138,7 @@ pub enum ColorGenerator {
         index: usize,
         uz: [Vec<(i64, f64)>; 2],
         vz: [Vec<(i64, f64)>; 2],
+        wz: [Vec<(i64, f64)>; 2],
     },
     Color(Color),
 }
@@ -63,13 +150,12 @@ impl ColorGenerator {
         right: usize,
         i: usize,
         x: [f64; 2],
-        inv_z: &Vec<f64>,
-        textures: &Vec<Texture>,
+        textures: &[Texture],
     ) -> Vec<Color> {
         let x_left = x[left].floor();
         let x_right = x[right].ceil();
         match &self {
-            ColorGenerator::Texture { index, uz, vz } => {
+            ColorGenerator::Texture { index, uz, vz, wz } => {
                 let texture = &textures[*index];
                 let uzscan =
                     util::interpolate(x_left as i64, uz[left][i].1, x_right as i64, uz[right][i].1)
@@ -78,12 +164,16 @@ impl ColorGenerator {
                     util::interpolate(x_left as i64, vz[left][i].1, x_right as i64, vz[right][i].1)
                         .map(|(_i, d)| d);
 
+                let wzscan =
+                    util::interpolate(x_left as i64, wz[left][i].1, x_right as i64, wz[right][i].1)
+                        .map(|(_i, d)| d);
+
                 uzscan
                     .zip(vzscan)
-                    .zip(inv_z.iter())
-                    .map(|((u, v), &inv_z)| {
-                        let u = u / inv_z;
-                        let v = v / inv_z;
+                    .zip(wzscan)
+                    .map(|((u, v), w)| {
+                        let u = u / w;
+                        let v = v / w;
                         texture.texel_color(u, v)
                     })
                     .collect_vec()
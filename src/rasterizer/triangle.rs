@@ -1,6 +1,5 @@
 use crate::rasterizer::point::Point;
-use crate::rasterizer::surface::Surface;
-use crate::rasterizer::texture::Texture;
+use crate::rasterizer::surface::{NormalMap, Surface};
 use crate::rasterizer::util;
 use crate::vec3::{Color, Vec3};
 use itertools;
@@ -12,46 +11,49 @@ pub struct Triangle {
     pub vertex_indices: [usize; 3],
     pub surface: Surface,
     pub normals: Option<[Vec3; 3]>,
+    pub normal_map: Option<NormalMap>,
 }
 
 impl Triangle {
-    pub fn new(
-        v1: usize,
-        v2: usize,
-        v3: usize,
-        surface: Surface,
-        n1: Vec3,
-        n2: Vec3,
-        n3: Vec3,
-    ) -> Triangle {
+    pub fn new_no_normals(v1: usize, v2: usize, v3: usize, surface: Surface) -> Triangle {
         Triangle {
             vertex_indices: [v1, v2, v3],
             surface,
-            normals: Some([n1, n2, n3]),
+            normals: None,
+            normal_map: None,
         }
     }
 
-    pub fn new_no_normals(v1: usize, v2: usize, v3: usize, surface: Surface) -> Triangle {
+    /// Creates a triangle covering part of this one, such as when it's clipped. Each of the new vertices is given as
+    /// its weights in this triangle, from which its normal and texture coordinates are interpolated.
+    pub fn clipped(&self, vertex_indices: [usize; 3], weights: [[f64; 3]; 3]) -> Triangle {
+        let normals = self.normals.map(|normals| {
+            weights.map(|w| normals[0] * w[0] + normals[1] * w[1] + normals[2] * w[2])
+        });
         Triangle {
-            vertex_indices: [v1, v2, v3],
-            surface,
-            normals: None,
+            vertex_indices,
+            surface: self.surface.clipped(weights),
+            normals,
+            normal_map: self
+                .normal_map
+                .as_ref()
+                .map(|normal_map| normal_map.clipped(weights)),
         }
     }
 
-    pub fn sorted_indexes_by_y(&self, vertexes: &Vec<Point>) -> [usize; 3] {
+    pub fn sorted_indexes_by_y(&self, vertexes: &[Point]) -> [usize; 3] {
         let mut indexes = [0, 1, 2];
         indexes.sort_by_key(|&i| vertexes[self.vertex_indices[i]].y);
         indexes
     }
 
-    pub fn normal(&self, vertices: &Vec<Vec3>) -> Vec3 {
+    pub fn normal(&self, vertices: &[Vec3]) -> Vec3 {
         let v1 = vertices[self.vertex_indices[1]] - &vertices[self.vertex_indices[0]];
         let v2 = vertices[self.vertex_indices[2]] - &vertices[self.vertex_indices[0]];
         v1.cross_product(&v2)
     }
 
-    pub fn center(&self, vertices: &Vec<Vec3>) -> Vec3 {
+    pub fn center(&self, vertices: &[Vec3]) -> Vec3 {
         (vertices[self.vertex_indices[0]]
             + vertices[self.vertex_indices[1]]
             + vertices[self.vertex_indices[2]])
@@ -1,32 +1,50 @@
-use crate::rasterizer::point::Point;
+use crate::rasterizer::camera::{Projection, Viewport};
+use crate::rasterizer::point::{Point, SubpixelPoint};
+use crate::rasterizer::rasterization::SUBPIXEL_SCALE;
 use crate::vec3::{Vec3, Vec4};
 use itertools::Itertools;
+use std::ops::{Add, Div, Sub};
 
-const VIEWPORT_SIZE: f64 = 1.;
 pub const PROJECTION_PLANE_Z: f64 = 1.;
 
-pub fn interpolate(i0: i64, d0: f64, i1: i64, d1: f64) -> impl Iterator<Item = (i64, f64)> {
+/// Values that can be interpolated linearly, such as depths and the light reflected from vertices.
+pub trait Interpolate:
+    Copy + Default + Add<Output = Self> + Sub<Output = Self> + Div<f64, Output = Self>
+{
+}
+
+impl<T> Interpolate for T where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T> + Div<f64, Output = T>
+{
+}
+
+pub fn interpolate<T: Interpolate>(
+    i0: i64,
+    d0: T,
+    i1: i64,
+    d1: T,
+) -> impl Iterator<Item = (i64, T)> {
     let a = if i0 == i1 {
-        0.
+        T::default()
     } else {
-        ((d1 - d0) as f64) / ((i1 - i0) as f64)
+        (d1 - d0) / ((i1 - i0) as f64)
     };
 
-    (i0..(i1 + 1)).scan(d0 as f64, move |d, i| {
+    (i0..(i1 + 1)).scan(d0, move |d, i| {
         let res = Some((i, *d));
-        *d += a;
+        *d = *d + a;
         res
     })
 }
 
-pub fn edge_interpolate(
+pub fn edge_interpolate<T: Interpolate>(
     y0: i64,
-    v0: f64,
+    v0: T,
     y1: i64,
-    v1: f64,
+    v1: T,
     y2: i64,
-    v2: f64,
-) -> [Vec<(i64, f64)>; 2] {
+    v2: T,
+) -> [Vec<(i64, T)>; 2] {
     let v01 = interpolate(y0, v0, y1, v1);
     let v02 = interpolate(y0, v0, y2, v2).collect_vec(); // Long size
     let v12 = interpolate(y1, v1, y2, v2);
@@ -36,15 +54,109 @@ pub fn edge_interpolate(
     [v02, v012]
 }
 
-pub fn project_vertex(v: &Vec4, canvas_width: i64, canvas_height: i64) -> Point {
-    let x = v[0] * PROJECTION_PLANE_Z / v[2] * canvas_width as f64 / VIEWPORT_SIZE;
-    let y = v[1] * PROJECTION_PLANE_Z / v[2] * canvas_height as f64 / VIEWPORT_SIZE;
+/// The barycentric weights of a point in the plane of a triangle, which are the areas of the triangles the point makes
+/// with each side over the area of the whole triangle.
+pub fn barycentric_weights(p: &Vec3, vertices: [&Vec3; 3]) -> [f64; 3] {
+    let normal = (vertices[1] - vertices[0]).cross_product(&(vertices[2] - vertices[0]));
+    let area = normal.dot(&normal);
+    [0, 1, 2].map(|i| {
+        let (a, b) = (vertices[(i + 1) % 3], vertices[(i + 2) % 3]);
+        (b - a).cross_product(&(p - a)).dot(&normal) / area
+    })
+}
+
+/// Projects a vertex onto the projection plane, in canvas units.
+fn project(v: &Vec4, viewport: &Viewport, canvas_width: i64, canvas_height: i64) -> (f64, f64) {
+    let (x, y) = match viewport.projection {
+        Projection::Perspective => (
+            v[0] * PROJECTION_PLANE_Z / v[2],
+            v[1] * PROJECTION_PLANE_Z / v[2],
+        ),
+        Projection::Orthographic => (v[0], v[1]),
+    };
+    (
+        x * canvas_width as f64 / viewport.width,
+        y * canvas_height as f64 / viewport.height,
+    )
+}
+
+pub fn project_vertex(
+    v: &Vec4,
+    viewport: &Viewport,
+    canvas_width: i64,
+    canvas_height: i64,
+) -> Point {
+    let (x, y) = project(v, viewport, canvas_width, canvas_height);
     Point::new(x as i64, y as i64, 1.)
 }
 
-pub fn unproject_vertex(x: f64, y: f64, inv_z: f64, canvas_width: i64, canvas_height: i64) -> Vec3 {
+pub fn project_vertex_subpixel(
+    v: &Vec4,
+    viewport: &Viewport,
+    canvas_width: i64,
+    canvas_height: i64,
+) -> SubpixelPoint {
+    let (x, y) = project(v, viewport, canvas_width, canvas_height);
+    let scale = SUBPIXEL_SCALE as f64;
+    SubpixelPoint::new((x * scale).round() as i64, (y * scale).round() as i64)
+}
+
+pub fn unproject_vertex(
+    x: f64,
+    y: f64,
+    inv_z: f64,
+    viewport: &Viewport,
+    canvas_width: i64,
+    canvas_height: i64,
+) -> Vec3 {
     let z = 1.0 / inv_z;
-    let ux = (x * z / PROJECTION_PLANE_Z) * (VIEWPORT_SIZE / canvas_width as f64);
-    let uy = (y * z / PROJECTION_PLANE_Z) * (VIEWPORT_SIZE / canvas_height as f64);
+    let (x, y) = match viewport.projection {
+        Projection::Perspective => (x * z / PROJECTION_PLANE_Z, y * z / PROJECTION_PLANE_Z),
+        Projection::Orthographic => (x, y),
+    };
+    let ux = x * (viewport.width / canvas_width as f64);
+    let uy = y * (viewport.height / canvas_height as f64);
     Vec3::new(ux, uy, z)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rasterizer::camera::{Camera, Projection, Viewport};
+    use crate::rasterizer::util::{barycentric_weights, project_vertex, unproject_vertex};
+    use crate::vec3::{Mat3, Vec3};
+
+    #[test]
+    fn test_project_non_square() {
+        let camera = Camera::new(Vec3::new(0., 0., 0.), Mat3::identity(), 90., 2., 1., 100.);
+        for projection in [Projection::Perspective, Projection::Orthographic] {
+            let viewport = Viewport {
+                projection,
+                ..camera.viewport
+            };
+
+            // A square facing the camera is square on the canvas.
+            let corner = project_vertex(&Vec3::new(1., 1., 2.).to_vec4(1.), &viewport, 400, 200);
+            assert_eq!(corner.x, corner.y);
+
+            let v = Vec3::new(0.5, -0.25, 2.);
+            let p = project_vertex(&v.to_vec4(1.), &viewport, 400, 200);
+            let u = unproject_vertex(p.x as f64, p.y as f64, 0.5, &viewport, 400, 200);
+            assert!((&u - &v).len() < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_barycentric_weights() {
+        let vertices = [
+            Vec3::new(0., 0., 2.),
+            Vec3::new(2., 0., 4.),
+            Vec3::new(0., 3., 2.),
+        ];
+        let vertices = [&vertices[0], &vertices[1], &vertices[2]];
+        assert_eq!(barycentric_weights(vertices[1], vertices), [0., 1., 0.]);
+
+        let weights = barycentric_weights(&Vec3::new(0.5, 0.75, 2.5), vertices);
+        let expected = [0.5, 0.25, 0.25];
+        assert!((0..3).all(|i| (weights[i] - expected[i]).abs() < 1e-9));
+    }
+}
@@ -1,3 +1,2 @@
-mod light;
 pub mod main;
 mod objects;
@@ -1,162 +1,161 @@
-use crate::raytracer::light::{Light, Scatter};
 use crate::raytracer::objects;
-use crate::raytracer::objects::{Intersection, Sphere};
-use crate::utils;
-use crate::vec3::{Color, Mat3, Point, Vec3};
-use wasm_bindgen::prelude::*;
-
-struct Scene {
-    camera: Camera,
-    lights: Vec<Light>,
-    objects: Vec<Sphere>,
-    viewport_width: f64,
-    viewport_height: f64,
-    projection_pane_d: f64,
+use crate::raytracer::objects::{Intersection, Object};
+use crate::renderer::{Framebuffer, Renderer, SceneCache, Statistics};
+use crate::scene::camera::{Camera, Projection};
+use crate::scene::light::Light;
+use crate::scene::mesh::TangentFrame;
+use crate::scene::Scene;
+use crate::vec3::{Color, Point, Vec3};
+use std::collections::HashMap;
+
+// The distance from the camera to the viewport, for a perspective camera.
+const PROJECTION_PLANE_D: f64 = 1.0;
+
+/// The width and height of the viewport for a canvas with the given aspect ratio.
+fn viewport_size(camera: &Camera, aspect_ratio: f64) -> (f64, f64) {
+    let height = match camera.projection {
+        Projection::Perspective { fov } => 2. * PROJECTION_PLANE_D * (fov.to_radians() / 2.).tan(),
+        Projection::Orthographic { height } => height,
+    };
+    (height * aspect_ratio, height)
 }
 
-fn new_scene() -> Scene {
-    let camera = Camera::new(
-        Vec3::new(3., 0., 1.),
-        Mat3::new([
-            [
-                std::f64::consts::FRAC_1_SQRT_2,
-                0.,
-                -std::f64::consts::FRAC_1_SQRT_2,
-            ],
-            [0., 1., 0.],
-            [
-                std::f64::consts::FRAC_1_SQRT_2,
-                0.,
-                std::f64::consts::FRAC_1_SQRT_2,
-            ],
-        ]),
-    );
-
-    let objects = vec![
-        Sphere::new(
-            Point::new(0.0, -1.0, 3.0),
-            1.0,
-            Color::new(255.0, 0.0, 0.0),
-            Scatter::Specular { shininess: 500. },
-            0.2,
-        ),
-        Sphere::new(
-            Point::new(2.0, 0.0, 4.0),
-            1.0,
-            Color::new(0.0, 0.0, 255.0),
-            Scatter::Specular { shininess: 500. },
-            0.3,
+/// Returns the origin and direction of the ray through the point (x, y) on the viewport.
+fn primary_ray(camera: &Camera, viewport_x: f64, viewport_y: f64) -> (Point, Vec3) {
+    match camera.projection {
+        // Rays leave the camera through each point on the viewport.
+        Projection::Perspective { .. } => (
+            camera.position,
+            &camera.orientation * Vec3::new(viewport_x, viewport_y, PROJECTION_PLANE_D),
         ),
-        Sphere::new(
-            Point::new(-2.0, 0.0, 4.0),
-            1.0,
-            Color::new(0.0, 255.0, 0.0),
-            Scatter::Specular { shininess: 10. },
-            0.4,
+        // Rays leave each point on the viewport parallel to the camera's z axis, with the viewport centered on the
+        // camera.
+        Projection::Orthographic { .. } => (
+            camera.position + &camera.orientation * Vec3::new(viewport_x, viewport_y, 0.),
+            &camera.orientation * Vec3::new(0., 0., PROJECTION_PLANE_D),
         ),
-        Sphere::new(
-            Point::new(0., -5001.0, 0.0),
-            5000.,
-            Color::new(255.0, 255.0, 0.0),
-            Scatter::Specular { shininess: 1000. },
-            0.5,
-        ),
-    ];
-
-    let lights = vec![
-        Light::Ambient { intensity: 0.2 },
-        Light::Point {
-            intensity: 0.6,
-            position: Vec3::new(2., 1., 0.),
-        },
-        Light::Directional {
-            intensity: 0.2,
-            direction: Vec3::new(1., 4., 4.),
-        },
-    ];
-
-    Scene {
-        camera,
-        lights,
-        objects,
-        viewport_width: 1.0,
-        viewport_height: 1.0,
-        projection_pane_d: 1.0,
     }
 }
 
-struct Camera {
-    position: Vec3,
-    rotation: Mat3,
+/// Renders scenes by tracing rays from the camera through each pixel, with shadows and reflections.
+pub struct Raytracer {
+    // How many times a ray can be reflected before the color of what it hits is taken as it is.
+    recursion_depth: u8,
+    background_color: Color,
+    // The tangent frames of each mesh that has a normal or height map, in its own space.
+    tangent_frames: SceneCache<HashMap<usize, Vec<[TangentFrame; 3]>>>,
 }
 
-impl Camera {
-    fn new(position: Vec3, rotation: Mat3) -> Camera {
-        Camera { position, rotation }
+impl Raytracer {
+    pub fn new(recursion_depth: u8, background_color: Color) -> Raytracer {
+        Raytracer {
+            recursion_depth,
+            background_color,
+            tangent_frames: SceneCache::default(),
+        }
     }
 }
 
-#[wasm_bindgen]
-pub fn raytracer(canvas_height: usize, canvas_width: usize) -> Vec<u8> {
-    utils::set_panic_hook();
-
-    let mut res = Vec::with_capacity(canvas_width * canvas_height * 4);
-    let Scene {
-        camera,
-        lights,
-        objects,
-        viewport_width,
-        viewport_height,
-        projection_pane_d,
-    } = new_scene();
-
-    let background_color = Color::new(0., 0., 0.);
-
-    let viewport_width_scale = viewport_width as f64 / canvas_width as f64;
-    let viewport_height_scale = viewport_height as f64 / canvas_height as f64;
-    for canvas_y in (0..canvas_height as usize).rev() {
-        let viewport_y = (canvas_y as f64 - canvas_height as f64 / 2.0) * viewport_height_scale;
-
-        for canvas_x in 0..canvas_width as usize {
-            let viewport_x = (canvas_x as f64 - canvas_width as f64 / 2.0) * viewport_width_scale;
-            let direction = &camera.rotation * Vec3::new(viewport_x, viewport_y, projection_pane_d);
-            let color = trace_ray(
-                &camera.position,
-                &direction,
-                &lights,
-                &objects,
-                background_color,
-                3,
-            );
+impl Default for Raytracer {
+    fn default() -> Raytracer {
+        Raytracer::new(3, Color::new(0., 0., 0.))
+    }
+}
+
+impl Renderer for Raytracer {
+    fn render(&mut self, scene: &Scene, framebuffer: &mut Framebuffer) -> Statistics {
+        self.render_preview(scene, framebuffer, 1)
+    }
 
-            res.push(color[0].clamp(0., 255.) as u8);
-            res.push(color[1].clamp(0., 255.) as u8);
-            res.push(color[2].clamp(0., 255.) as u8);
-            res.push(255);
+    /// Traces one ray for each `block_size` by `block_size` block of pixels, filling the block with its color.
+    fn render_preview(
+        &mut self,
+        scene: &Scene,
+        framebuffer: &mut Framebuffer,
+        block_size: usize,
+    ) -> Statistics {
+        let block_size = block_size.max(1);
+        let (canvas_height, canvas_width) = (framebuffer.height, framebuffer.width);
+        let Scene { camera, lights, .. } = scene;
+        let objects = Object::from_scene(scene, self.tangent_frames.get(scene));
+        let (viewport_width, viewport_height) = viewport_size(camera, framebuffer.aspect_ratio());
+        let mut rays = 0;
+
+        let viewport_width_scale = viewport_width / canvas_width as f64;
+        let viewport_height_scale = viewport_height / canvas_height as f64;
+        for row in (0..canvas_height).step_by(block_size) {
+            let canvas_y = canvas_height - row - 1;
+            let viewport_y = (canvas_y as f64 - canvas_height as f64 / 2.0) * viewport_height_scale;
+
+            for column in (0..canvas_width).step_by(block_size) {
+                let viewport_x = (column as f64 - canvas_width as f64 / 2.0) * viewport_width_scale;
+                let (origin, direction) = primary_ray(camera, viewport_x, viewport_y);
+                // Colors are kept linear and unclamped, with 1 for white, so highlights can be tone mapped later.
+                let color = trace_ray(
+                    &origin,
+                    &direction,
+                    (camera.near, camera.far),
+                    lights,
+                    &objects,
+                    self.background_color,
+                    self.recursion_depth,
+                    &mut rays,
+                );
+
+                for y in row..(row + block_size).min(canvas_height) {
+                    for x in column..(column + block_size).min(canvas_width) {
+                        let offset = (y * canvas_width + x) * 4;
+                        framebuffer.colors[offset] = (color[0] / 255.) as f32;
+                        framebuffer.colors[offset + 1] = (color[1] / 255.) as f32;
+                        framebuffer.colors[offset + 2] = (color[2] / 255.) as f32;
+                        framebuffer.colors[offset + 3] = 1.;
+                    }
+                }
+            }
+        }
+
+        Statistics {
+            objects: objects.len(),
+            rays,
+            ..Statistics::default()
         }
     }
 
-    res
+    fn is_progressive(&self) -> bool {
+        true
+    }
 }
 
+/// Traces a ray into the scene, returning the color it sees. Only hits `t_range` times the direction away count. Adds
+/// the rays it traces, including this one, to `rays`.
 fn trace_ray(
     origin: &Point,
     direction: &Vec3,
+    t_range: (f64, f64),
     lights: &Vec<Light>,
-    objects: &Vec<Sphere>,
+    objects: &Vec<Object>,
     background_color: Vec3,
     recursions_remaining: u8,
+    rays: &mut usize,
 ) -> Color {
+    *rays += 1;
     if let Some(Intersection {
         p,
         t: _t,
         normal,
         color,
+        specular,
         scatter,
         reflective,
-    }) = objects::closest_intersection(&objects, origin, direction, 1.0, f64::INFINITY)
+    }) = objects::closest_intersection(objects, origin, direction, t_range.0, t_range.1)
     {
-        let local_color = color * scatter.intensity(lights, objects, direction, &p, &normal);
+        let in_shadow = |l: &Vec3, t_max: f64| {
+            *rays += 1;
+            objects::closest_intersection(objects, &p, l, 0.001, t_max).is_some()
+        };
+        let local_color = scatter
+            .intensity(lights, &p, &normal, &-direction, in_shadow)
+            .shade(color, specular);
         if recursions_remaining == 0 || reflective == 0.0 {
             local_color
         } else {
@@ -164,10 +163,13 @@ fn trace_ray(
             let reflected_color = trace_ray(
                 &p,
                 &reflected_direction,
+                // The camera's clipping planes only apply to what it sees directly.
+                (0.001, f64::INFINITY),
                 lights,
                 objects,
                 background_color,
                 recursions_remaining - 1,
+                rays,
             );
             local_color * (1.0 - reflective) + reflected_color * reflective
         }
@@ -175,3 +177,177 @@ fn trace_ray(
         background_color
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::color::ToneMapping;
+    use crate::golden::assert_matches_golden;
+    use crate::raytracer::main::{primary_ray, Raytracer};
+    use crate::renderer::{Framebuffer, Renderer};
+    use crate::scene::camera::{Camera, Projection, DEFAULT_FOV};
+    use crate::scene::examples::Example;
+    use crate::scene::light::{Light, Scatter};
+    use crate::scene::material::Material;
+    use crate::scene::node::{Node, Shape};
+    use crate::scene::Scene;
+    use crate::vec3::{Color, Mat3, Vec3};
+
+    #[test]
+    fn test_non_square_canvas() {
+        // The middle rows of a wide canvas see what a square canvas of the same height sees.
+        let scene = Example::Spheres.scene();
+        let mut square = Framebuffer::new(20, 20);
+        let mut wide = Framebuffer::new(20, 40);
+        Raytracer::default().render(&scene, &mut square);
+        Raytracer::default().render(&scene, &mut wide);
+        for y in 0..20 {
+            let row = &square.colors[y * 80..(y + 1) * 80];
+            assert_eq!(&wide.colors[y * 160 + 40..y * 160 + 120], row);
+        }
+    }
+
+    #[test]
+    fn test_render_preview() {
+        let scene = Example::Spheres.scene();
+        let mut raytracer = Raytracer::default();
+        let mut framebuffer = Framebuffer::new(20, 30);
+        raytracer.render(&scene, &mut framebuffer);
+        let full = framebuffer.colors.clone();
+
+        // Each block takes the color of its top left pixel, including the partial blocks at the edges.
+        let statistics = raytracer.render_preview(&scene, &mut framebuffer, 8);
+        let coarse = &framebuffer.colors;
+        for y in 0..20 {
+            for x in 0..30 {
+                let offset = (y * 30 + x) * 4;
+                let block_offset = ((y / 8 * 8) * 30 + x / 8 * 8) * 4;
+                assert_eq!(
+                    coarse[offset..offset + 4],
+                    full[block_offset..block_offset + 4]
+                );
+            }
+        }
+        assert!(statistics.rays >= 3 * 4);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut framebuffer = Framebuffer::new(20, 30);
+        let statistics = Raytracer::default().render(&Example::Spheres.scene(), &mut framebuffer);
+
+        // There's at least one ray for each pixel, and shadow and reflection rays wherever one hits a sphere.
+        assert_eq!(statistics.objects, 4);
+        assert!(statistics.rays > 20 * 30);
+        assert_eq!(statistics.triangles, 0);
+    }
+
+    #[test]
+    fn test_orthographic_primary_rays() {
+        let orientation = Mat3::new_euler_rotation_matrix(30., -20., 0.);
+        let camera = Camera::new(
+            Vec3::new(1., 2., 3.),
+            orientation,
+            Projection::Orthographic { height: 4. },
+            0.1,
+            100.,
+        );
+
+        // Every ray runs along the camera's z axis, from the point of the viewport it goes through.
+        let forward = &orientation * Vec3::new(0., 0., 1.);
+        for (x, y) in [(0., 0.), (-2., 1.5), (1., -0.5)] {
+            let (origin, direction) = primary_ray(&camera, x, y);
+            assert!((&(direction / direction.len()) - &forward).len() < 1e-9);
+            let offset = &origin - &camera.position;
+            assert!(offset.dot(&forward).abs() < 1e-9);
+            assert!((offset.len() - (x * x + y * y).sqrt()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_orthographic() {
+        // A red sphere close to the camera and a green one of the same size much further away, lit evenly.
+        let sphere = |material, translation| {
+            Node::new(
+                Shape::Sphere,
+                material,
+                Vec3::new(0.5, 0.5, 0.5),
+                Mat3::identity(),
+                translation,
+            )
+        };
+        let scene = |projection| {
+            Scene::new(
+                Camera::new(
+                    Vec3::new(0., 0., 0.),
+                    Mat3::identity(),
+                    projection,
+                    0.1,
+                    100.,
+                ),
+                vec![Light::Ambient {
+                    intensity: Color::new(1., 1., 1.),
+                }],
+                vec![
+                    Material::new(Color::new(255., 0., 0.), Scatter::Diffuse, 0.),
+                    Material::new(Color::new(0., 255., 0.), Scatter::Diffuse, 0.),
+                ],
+                vec![],
+                vec![
+                    sphere(0, Vec3::new(-1., 0., 3.)),
+                    sphere(1, Vec3::new(1., 0., 10.)),
+                ],
+                vec![],
+            )
+        };
+        let count_red_and_green = |colors: &[f32]| {
+            let count = |channel: usize| {
+                colors
+                    .chunks(4)
+                    .filter(|pixel| pixel[channel] > 0.5)
+                    .count()
+            };
+            (count(0), count(1))
+        };
+        let mut framebuffer = Framebuffer::new(40, 80);
+
+        // Seen in perspective, the further sphere looks much smaller.
+        Raytracer::default().render(
+            &scene(Projection::Perspective { fov: DEFAULT_FOV }),
+            &mut framebuffer,
+        );
+        let (near, far) = count_red_and_green(&framebuffer.colors);
+        assert!(far * 4 < near, "{} {}", near, far);
+
+        // Seen orthographically, both spheres cover the same pixels, just moved over.
+        Raytracer::default().render(
+            &scene(Projection::Orthographic { height: 4. }),
+            &mut framebuffer,
+        );
+        let (near, far) = count_red_and_green(&framebuffer.colors);
+        assert!(near > 60, "{}", near);
+        assert_eq!(near, far);
+    }
+
+    #[test]
+    fn test_golden_images() {
+        let mut scene = Example::Spheres.scene();
+        let mut raytracer = Raytracer::default();
+        let mut framebuffer = Framebuffer::new(120, 160);
+        raytracer.render(&scene, &mut framebuffer);
+        framebuffer.tone_map(ToneMapping::default(), 1.);
+        assert_matches_golden("raytracer", &framebuffer.pixels, 160, 120);
+
+        scene.animate(&Example::Spheres.timeline(), 0.5);
+        raytracer.render(&scene, &mut framebuffer);
+        framebuffer.tone_map(ToneMapping::default(), 1.);
+        assert_matches_golden("raytracer-animated", &framebuffer.pixels, 160, 120);
+
+        raytracer.render(&Example::Materials.scene(), &mut framebuffer);
+        framebuffer.tone_map(ToneMapping::default(), 1.);
+        assert_matches_golden("raytracer-materials", &framebuffer.pixels, 160, 120);
+
+        raytracer.render(&Example::Cobblestones.scene(), &mut framebuffer);
+        framebuffer.tone_map(ToneMapping::default(), 1.);
+        assert_matches_golden("raytracer-cobblestones", &framebuffer.pixels, 160, 120);
+    }
+}
@@ -1,11 +1,17 @@
-use crate::raytracer::light::Scatter;
+use crate::scene::light::Scatter;
+use crate::scene::mesh::TangentFrame;
+use crate::scene::node::Shape;
+use crate::scene::texture::Texture;
+use crate::scene::Scene;
 use crate::vec3::{Color, Point, Vec3};
+use std::collections::HashMap;
 
 pub struct Intersection {
     pub p: Point,
     pub t: f64,
     pub normal: Vec3,
     pub color: Color,
+    pub specular: Color,
     pub scatter: Scatter,
     pub reflective: f64,
 }
@@ -14,6 +20,7 @@ pub struct Sphere {
     pub center: Point,
     pub radius: f64,
     pub color: Color,
+    pub specular: Color,
     pub scatter: Scatter,
     pub reflective: f64,
 }
@@ -23,6 +30,7 @@ impl Sphere {
         center: Point,
         radius: f64,
         color: Color,
+        specular: Color,
         scatter: Scatter,
         reflective: f64,
     ) -> Sphere {
@@ -30,6 +38,7 @@ impl Sphere {
             center,
             radius,
             color,
+            specular,
             scatter,
             reflective,
         }
@@ -71,6 +80,7 @@ impl Sphere {
                         t,
                         normal,
                         color,
+                        specular: self.specular,
                         scatter: self.scatter,
                         reflective: self.reflective,
                     }
@@ -79,8 +89,204 @@ impl Sphere {
     }
 }
 
+/// A texture that tilts a triangle's normals, and the tangent frames at the triangle's corners that orient it.
+pub struct NormalMap<'a> {
+    pub texture: &'a Texture,
+    // When the texture is a height map instead, how high its white stands over the surface, as a fraction of its
+    // width.
+    pub depth: Option<f64>,
+    pub frames: [TangentFrame; 3],
+}
+
+/// A triangle of a mesh, in world space. Both of its sides are lit, so meshes don't need to be closed.
+pub struct Triangle<'a> {
+    pub vertices: [Point; 3],
+    pub normals: Option<[Vec3; 3]>,
+    pub uvs: [(f64, f64); 3],
+    pub color: Color,
+    pub texture: Option<&'a Texture>,
+    pub normal_map: Option<NormalMap<'a>>,
+    pub specular: Color,
+    pub scatter: Scatter,
+    pub reflective: f64,
+}
+
+impl<'a> Triangle<'a> {
+    /// Intersects the ray with the triangle using the Möller-Trumbore algorithm, which finds the barycentric
+    /// coordinates of the hit without first intersecting the triangle's plane.
+    pub fn intersect(
+        &self,
+        origin: &Point,
+        direction: &Vec3,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<Intersection> {
+        let [v0, v1, v2] = &self.vertices;
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+        let p = direction.cross_product(&e2);
+        let det = e1.dot(&p);
+        if det.abs() < 1e-12 {
+            // The ray is parallel to the triangle.
+            return None;
+        }
+
+        let s = origin - v0;
+        let u = s.dot(&p) / det;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+        let q = s.cross_product(&e1);
+        let v = direction.dot(&q) / det;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+        let t = e2.dot(&q) / det;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let weights = [1. - u - v, u, v];
+        let normal = match self.normals {
+            Some(n) => n[0] * weights[0] + n[1] * weights[1] + n[2] * weights[2],
+            None => e1.cross_product(&e2),
+        };
+        let normal = match &self.normal_map {
+            Some(normal_map) => {
+                let (u, v) = self.uv(weights);
+                let (u, v) = (u.clamp(0., 1.), v.clamp(0., 1.));
+                let mapped = match normal_map.depth {
+                    Some(depth) => normal_map.texture.height_normal(u, v, depth),
+                    None => normal_map.texture.texel_normal(u, v),
+                };
+                TangentFrame::interpolate(&normal_map.frames, weights).perturb(&normal, &mapped)
+            }
+            None => normal / normal.len(),
+        };
+        // Light the side the ray hit.
+        let normal = if normal.dot(direction) > 0. {
+            -normal
+        } else {
+            normal
+        };
+
+        let color = match self.texture {
+            Some(texture) => {
+                let (u, v) = self.uv(weights);
+                texture.texel_color(u, v)
+            }
+            None => self.color,
+        };
+
+        Some(Intersection {
+            p: origin + direction * t,
+            t,
+            normal,
+            color,
+            specular: self.specular,
+            scatter: self.scatter,
+            reflective: self.reflective,
+        })
+    }
+
+    /// The texture coordinates at the point with the given barycentric `weights`.
+    fn uv(&self, weights: [f64; 3]) -> (f64, f64) {
+        let uvs = &self.uvs;
+        (
+            uvs[0].0 * weights[0] + uvs[1].0 * weights[1] + uvs[2].0 * weights[2],
+            uvs[0].1 * weights[0] + uvs[1].1 * weights[1] + uvs[2].1 * weights[2],
+        )
+    }
+}
+
+pub enum Object<'a> {
+    Sphere(Sphere),
+    // Triangles are boxed, as their normal maps make them much larger than spheres.
+    Triangle(Box<Triangle<'a>>),
+}
+
+impl<'a> Object<'a> {
+    /// The scene's shapes as objects to trace rays against, in world space. The tangent frames of the meshes that need
+    /// them are taken from `tangent_frames`, by the mesh's index, and found and added to it when they aren't there yet.
+    pub fn from_scene(
+        scene: &'a Scene,
+        tangent_frames: &mut HashMap<usize, Vec<[TangentFrame; 3]>>,
+    ) -> Vec<Object<'a>> {
+        let mut objects = Vec::new();
+        for object in scene.objects() {
+            let material = &scene.materials()[object.material];
+            let transformation = &object.transformation;
+            match object.shape {
+                Shape::Sphere => objects.push(Object::Sphere(Sphere::new(
+                    (transformation * Point::new(0., 0., 0.).to_vec4(1.)).to_vec3(),
+                    transformation.to_mat3().max_stretch(),
+                    material.color,
+                    material.specular,
+                    material.scatter,
+                    material.reflective,
+                ))),
+                Shape::Mesh(index) => {
+                    let mesh = &scene.meshes()[index];
+                    let vertices: Vec<Point> = mesh
+                        .vertices
+                        .iter()
+                        .map(|v| (transformation * v.to_vec4(1.)).to_vec3())
+                        .collect();
+                    let normal_matrix = transformation.normal_matrix();
+                    let tangent_matrix = transformation.to_mat3();
+                    let frames = material.bump().map(|_| {
+                        &*tangent_frames
+                            .entry(index)
+                            .or_insert_with(|| mesh.tangent_frames())
+                    });
+                    for (f, face) in mesh.faces.iter().enumerate() {
+                        let normals = match (face.normals, normal_matrix) {
+                            (Some(normals), Some(normal_matrix)) => {
+                                Some(normals.map(|n| &normal_matrix * n))
+                            }
+                            _ => None,
+                        };
+                        objects.push(Object::Triangle(Box::new(Triangle {
+                            vertices: face.vertices.map(|i| vertices[i]),
+                            normals,
+                            uvs: face.uvs,
+                            color: material.color,
+                            texture: material.texture.map(|i| &scene.textures()[i]),
+                            normal_map: frames.zip(material.bump()).map(|(frames, (i, depth))| {
+                                NormalMap {
+                                    texture: &scene.textures()[i],
+                                    depth,
+                                    frames: frames[f]
+                                        .map(|frame| frame.transformed(&tangent_matrix)),
+                                }
+                            }),
+                            specular: material.specular,
+                            scatter: material.scatter,
+                            reflective: material.reflective,
+                        })));
+                    }
+                }
+            }
+        }
+        objects
+    }
+
+    pub fn intersect(
+        &self,
+        origin: &Point,
+        direction: &Vec3,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<Intersection> {
+        match self {
+            Object::Sphere(sphere) => sphere.intersect(origin, direction, t_min, t_max),
+            Object::Triangle(triangle) => triangle.intersect(origin, direction, t_min, t_max),
+        }
+    }
+}
+
 pub fn closest_intersection(
-    objects: &Vec<Sphere>,
+    objects: &Vec<Object>,
     origin: &Point,
     direction: &Vec3,
     t_min: f64,
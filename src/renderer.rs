@@ -0,0 +1,152 @@
+use crate::color::{encode_srgb, ToneMapping};
+use crate::rasterizer::main::Rasterizer;
+use crate::raytracer::main::Raytracer;
+use crate::scene::Scene;
+use wasm_bindgen::prelude::*;
+
+/// What an image is rendered into, row by row from the top left. Renderers write linear colors, which are tone mapped
+/// into pixels for display.
+pub struct Framebuffer {
+    pub height: usize,
+    pub width: usize,
+    // Linear RGBA colors, where 1 is white. Highlights can be brighter than white until they're tone mapped.
+    pub colors: Vec<f32>,
+    // The tone mapped, sRGB encoded RGBA pixels.
+    pub pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(height: usize, width: usize) -> Framebuffer {
+        Framebuffer {
+            height,
+            width,
+            colors: vec![0.; width * height * 4],
+            pixels: vec![0; width * height * 4],
+        }
+    }
+
+    /// Clears the framebuffer to transparent black.
+    pub fn clear(&mut self) {
+        self.colors.fill(0.);
+        self.pixels.fill(0);
+    }
+
+    /// Tone maps the colors into the pixels, after scaling them by the exposure.
+    pub fn tone_map(&mut self, tone_mapping: ToneMapping, exposure: f64) {
+        for (color, pixel) in self.colors.chunks(4).zip(self.pixels.chunks_mut(4)) {
+            for c in 0..3 {
+                let mapped = tone_mapping.map(color[c] as f64 * exposure);
+                pixel[c] = (encode_srgb(mapped) * 255.).round() as u8;
+            }
+            // Coverage is already linear, so it's neither tone mapped nor encoded.
+            pixel[3] = (color[3].clamp(0., 1.) * 255.).round() as u8;
+        }
+    }
+
+    /// The width of the image divided by its height.
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+/// What it took to render a frame. Each renderer counts the work it does, and leaves the rest at 0.
+#[wasm_bindgen]
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Statistics {
+    // The shapes in the scene that were rendered, rather than culled.
+    pub objects: usize,
+    // The triangles that were rasterized, after clipping and backface culling.
+    pub triangles: usize,
+    // The rays that were traced, including shadow and reflection rays.
+    pub rays: usize,
+}
+
+/// A way of rendering scenes into framebuffers. Its settings are given when it's created.
+pub trait Renderer {
+    /// Renders the scene into the framebuffer's colors, replacing everything in them. The pixels are left to be tone
+    /// mapped.
+    fn render(&mut self, scene: &Scene, framebuffer: &mut Framebuffer) -> Statistics;
+
+    /// Renders a cheaper preview of the scene, with about one sample for each `block_size` by `block_size` block of
+    /// pixels. Rendering with smaller and smaller blocks refines the image. Renderers that are fast enough to render
+    /// every frame in full do so.
+    fn render_preview(
+        &mut self,
+        scene: &Scene,
+        framebuffer: &mut Framebuffer,
+        _block_size: usize,
+    ) -> Statistics {
+        self.render(scene, framebuffer)
+    }
+
+    /// Whether previews are cheaper than full renders, so an interactive view should refine its image over several
+    /// frames rather than render it in full every time it changes.
+    fn is_progressive(&self) -> bool {
+        false
+    }
+}
+
+/// What a renderer builds from a scene's meshes, materials and textures, such as the rasterizer's models, kept between
+/// frames. It's only thrown away when they change, so moving the camera or the nodes doesn't build it again.
+#[derive(Default)]
+pub struct SceneCache<T> {
+    // The version of the scene the cached values were built from. Scenes start at 1, so nothing matches at first.
+    version: u64,
+    cached: T,
+}
+
+impl<T: Default> SceneCache<T> {
+    /// The values cached for the scene, emptied first if its meshes, materials or textures changed since the last call.
+    pub fn get(&mut self, scene: &Scene) -> &mut T {
+        if self.version != scene.version() {
+            self.version = scene.version();
+            self.cached = T::default();
+        }
+        &mut self.cached
+    }
+}
+
+/// The renderers, with their default settings.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Backend {
+    // Projects triangles onto the screen and fills them in.
+    Rasterizer,
+
+    // Traces rays from the camera through each pixel, with shadows and reflections.
+    Raytracer,
+}
+
+impl Backend {
+    pub fn renderer(&self) -> Box<dyn Renderer> {
+        match self {
+            Backend::Rasterizer => Box::new(Rasterizer::default()),
+            Backend::Raytracer => Box::new(Raytracer::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::SceneCache;
+    use crate::scene::examples::Example;
+    use crate::vec3::{Color, Vec3};
+
+    #[test]
+    fn test_scene_cache() {
+        let mut scene = Example::Crates.scene();
+        let mut cache: SceneCache<Vec<usize>> = SceneCache::default();
+        cache.get(&scene).push(1);
+
+        // Moving things around keeps what was built, while changing what it was built from throws it away.
+        scene.nodes[0].translation = Vec3::new(1., 2., 3.);
+        scene.camera.position = Vec3::new(0., 1., 0.);
+        assert_eq!(cache.get(&scene), &vec![1]);
+        scene.materials_mut()[0].color = Color::new(10., 20., 30.);
+        assert!(cache.get(&scene).is_empty());
+
+        // Another scene is never taken for the one the values were built from, even if it's just like it.
+        cache.get(&scene).push(2);
+        assert!(cache.get(&Example::Crates.scene()).is_empty());
+    }
+}
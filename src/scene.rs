@@ -0,0 +1,140 @@
+pub mod camera;
+pub mod examples;
+pub mod file;
+pub mod light;
+pub mod material;
+pub mod mesh;
+pub mod node;
+pub mod texture;
+
+use crate::animation::{Channel, Target, Timeline};
+use crate::scene::camera::Camera;
+use crate::scene::light::Light;
+use crate::scene::material::Material;
+use crate::scene::mesh::Mesh;
+use crate::scene::node::{Node, Object};
+use crate::scene::texture::Texture;
+use crate::vec3::Mat4;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// The next version to give a scene's meshes, materials and textures. Versions are unique across scenes, so what was
+// built from one scene is never mistaken for what another one would build.
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+/// A scene either renderer can render. Everything in it is in world space, and nothing depends on the size of the
+/// image it's rendered to.
+pub struct Scene {
+    pub camera: Camera,
+    pub lights: Vec<Light>,
+    materials: Vec<Material>,
+    meshes: Vec<Mesh>,
+    pub nodes: Vec<Node>,
+    textures: Vec<Texture>,
+    // Changes whenever the meshes, materials or textures may have, so renderers know when to rebuild what they built
+    // from them.
+    version: u64,
+}
+
+impl Scene {
+    pub fn new(
+        camera: Camera,
+        lights: Vec<Light>,
+        materials: Vec<Material>,
+        meshes: Vec<Mesh>,
+        nodes: Vec<Node>,
+        textures: Vec<Texture>,
+    ) -> Scene {
+        Scene {
+            camera,
+            lights,
+            materials,
+            meshes,
+            nodes,
+            textures,
+            version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn materials(&self) -> &[Material] {
+        &self.materials
+    }
+
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+
+    pub fn textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    /// The materials, to change. Changing them gives the scene a new version.
+    pub fn materials_mut(&mut self) -> &mut Vec<Material> {
+        self.bump_version();
+        &mut self.materials
+    }
+
+    /// The meshes, to change. Changing them gives the scene a new version.
+    pub fn meshes_mut(&mut self) -> &mut Vec<Mesh> {
+        self.bump_version();
+        &mut self.meshes
+    }
+
+    /// The textures, to change. Changing them gives the scene a new version.
+    pub fn textures_mut(&mut self) -> &mut Vec<Texture> {
+        self.bump_version();
+        &mut self.textures
+    }
+
+    /// Identifies the scene's meshes, materials and textures as they are now. No other scene, nor this one after they
+    /// change, has the same version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn bump_version(&mut self) {
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+    }
+    /// Flattens the scene graph into the shapes to render, each placed in the world.
+    pub fn objects(&self) -> Vec<Object> {
+        let mut objects = Vec::new();
+        for node in self.nodes.iter() {
+            node.collect_objects(&Mat4::identity(), &mut objects);
+        }
+        objects
+    }
+
+    /// Poses the scene as it is at `time` in the timeline. Properties without a track keep their current values.
+    pub fn animate(&mut self, timeline: &Timeline, time: f64) {
+        for (target, channel) in timeline.channels.iter() {
+            match target {
+                Target::Camera => match channel {
+                    Channel::Translation(track) => self.camera.position = track.sample(time),
+                    Channel::Rotation(track) => self.camera.orientation = track.sample(time).into(),
+                    _ => {}
+                },
+                Target::Node(path) => {
+                    let node = match path.split_first() {
+                        Some((&i, path)) => self
+                            .nodes
+                            .get_mut(i)
+                            .and_then(|node| node.descendant_mut(path)),
+                        None => None,
+                    };
+                    if let Some(node) = node {
+                        match channel {
+                            Channel::Translation(track) => node.translation = track.sample(time),
+                            Channel::Rotation(track) => node.rotation = track.sample(time).into(),
+                            Channel::Scale(track) => node.scale = track.sample(time),
+                            _ => {}
+                        }
+                    }
+                }
+                Target::Light(i) => {
+                    if let Some(light) = self.lights.get_mut(*i) {
+                        light.animate(channel, time);
+                    }
+                }
+            }
+        }
+    }
+}
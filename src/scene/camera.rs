@@ -0,0 +1,62 @@
+use crate::vec3::{Mat3, Vec3};
+use serde::Deserialize;
+
+/// The vertical field of view, in degrees, that makes the view as tall as it is far from the camera: 2 atan(1/2). The
+/// example scenes are framed for it, and scene files that leave the field of view out get it.
+pub const DEFAULT_FOV: f64 = 53.13010235415598;
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+pub enum Projection {
+    // Rays converge on the camera, so distant objects look smaller. `fov` is the vertical field of view, in degrees.
+    Perspective {
+        #[serde(default = "Projection::default_fov")]
+        fov: f64,
+    },
+
+    // Rays are parallel to the camera's z axis, so objects look the same size however far away they are. `height` is
+    // the height of the view, in the same units as the scene.
+    Orthographic {
+        height: f64,
+    },
+}
+
+impl Projection {
+    fn default_fov() -> f64 {
+        DEFAULT_FOV
+    }
+}
+
+/// Where the scene is seen from. The width of the view follows from the aspect ratio of the image being rendered.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Camera {
+    pub position: Vec3,
+    pub orientation: Mat3,
+    pub projection: Projection,
+    // The distances from the camera between which objects are rendered. Scene files can leave out the far one, which
+    // then has no limit.
+    pub near: f64,
+    #[serde(default = "Camera::no_far_limit")]
+    pub far: f64,
+}
+
+impl Camera {
+    pub fn new(
+        position: Vec3,
+        orientation: Mat3,
+        projection: Projection,
+        near: f64,
+        far: f64,
+    ) -> Camera {
+        Camera {
+            position,
+            orientation,
+            projection,
+            near,
+            far,
+        }
+    }
+
+    fn no_far_limit() -> f64 {
+        f64::INFINITY
+    }
+}
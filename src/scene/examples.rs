@@ -0,0 +1,550 @@
+use crate::animation::{Channel, Interpolation, Target, Timeline, Track};
+use crate::controls::CameraControls;
+use crate::scene::camera::{Camera, Projection, DEFAULT_FOV};
+use crate::scene::light::{Light, Scatter};
+use crate::scene::material::{HeightMap, Material};
+use crate::scene::mesh::{Face, Mesh};
+use crate::scene::node::{Node, Shape};
+use crate::scene::texture::Texture;
+use crate::scene::Scene;
+use crate::vec3::{Color, Mat3, Point, Quat, Vec3};
+use wasm_bindgen::prelude::*;
+
+const CRATE_BYTES: &[u8; 318447] = include_bytes!("crate-texture.jpg");
+
+/// The built-in scenes. Either renderer can render any of them.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Example {
+    // Three wooden crates, one of them turning.
+    Crates,
+
+    // Three shiny spheres on a yellow ground, one of them bouncing.
+    Spheres,
+
+    // Rows of gold and red plastic spheres, from smooth to rough, lit by a warm light moving across them and a cool
+    // light from behind the camera.
+    Materials,
+
+    // A stone block on a cobbled floor, lit by a light circling low over them. The floor's stones are raised by a
+    // height map, and the block's are drawn in a normal map.
+    Cobblestones,
+}
+
+impl Example {
+    pub fn scene(&self) -> Scene {
+        match self {
+            Example::Crates => crates(),
+            Example::Spheres => spheres(),
+            Example::Materials => materials(),
+            Example::Cobblestones => cobblestones(),
+        }
+    }
+
+    pub fn timeline(&self) -> Timeline {
+        match self {
+            Example::Crates => crates_timeline(),
+            Example::Spheres => spheres_timeline(),
+            Example::Materials => materials_timeline(),
+            Example::Cobblestones => cobblestones_timeline(),
+        }
+    }
+
+    /// Controls that start at the scene's camera, orbiting the middle of what it's looking at.
+    pub fn controls(&self) -> CameraControls {
+        let position = self.scene().camera.position;
+        match self {
+            Example::Crates => CameraControls::new(position, -30., 0., 6.),
+            Example::Spheres => CameraControls::new(position, 45., 0., 18f64.sqrt()),
+            Example::Materials => CameraControls::new(position, 0., 0., 4.5),
+            Example::Cobblestones => CameraControls::new(position, 0., 30., 5.),
+        }
+    }
+}
+
+fn cube() -> Mesh {
+    const UPPER: [(f64, f64); 3] = [(0., 0.), (1., 0.), (1., 1.)];
+    const LOWER: [(f64, f64); 3] = [(0., 0.), (1., 1.), (0., 1.)];
+    let face = |vertices, normal: Vec3, uvs| Face::new(vertices, Some([normal; 3]), uvs);
+
+    let front = Vec3::new(0., 0., 1.);
+    let left = Vec3::new(-1., 0., 0.);
+    let bottom = Vec3::new(0., -1., 0.);
+    let right = Vec3::new(1., 0., 0.);
+    let top = Vec3::new(0., 1., 0.);
+    let back = Vec3::new(0., 0., -1.);
+    Mesh::new(
+        vec![
+            Vec3::new(1., 1., 1.),
+            Vec3::new(-1., 1., 1.),
+            Vec3::new(-1., -1., 1.),
+            Vec3::new(1., -1., 1.),
+            Vec3::new(1., 1., -1.),
+            Vec3::new(-1., 1., -1.),
+            Vec3::new(-1., -1., -1.),
+            Vec3::new(1., -1., -1.),
+        ],
+        vec![
+            face([0, 1, 2], front, UPPER),
+            face([0, 2, 3], front, LOWER),
+            face([1, 5, 6], left, UPPER),
+            face([1, 6, 2], left, LOWER),
+            face([2, 6, 7], bottom, LOWER),
+            face([2, 7, 3], bottom, LOWER),
+            face([4, 0, 3], right, UPPER),
+            face([4, 1, 0], top, UPPER),
+            face([4, 3, 7], right, LOWER),
+            face([4, 5, 1], top, LOWER),
+            face([5, 4, 7], back, UPPER),
+            face([5, 7, 6], back, LOWER),
+        ],
+    )
+}
+
+fn crates() -> Scene {
+    let crate_texture = Texture::from_bytes(CRATE_BYTES).unwrap();
+    let camera = Camera::new(
+        Vec3::new(-3., 1.0, 2.0),
+        Mat3::new_euler_rotation_matrix(-30., 0., 0.),
+        Projection::Perspective { fov: DEFAULT_FOV },
+        1.,
+        100.,
+    );
+
+    Scene::new(
+        camera,
+        vec![
+            Light::Ambient {
+                intensity: Color::new(0.2, 0.2, 0.2),
+            },
+            Light::Directional {
+                intensity: Color::new(0.2, 0.2, 0.2),
+                direction: Vec3::new(-1., 0., 1.),
+            },
+            Light::Point {
+                intensity: Color::new(0.6, 0.6, 0.6),
+                position: Vec3::new(-3., 2., -10.),
+            },
+        ],
+        vec![Material::new_textured(
+            0,
+            Scatter::Specular { shininess: 50.0 },
+            0.,
+        )],
+        vec![cube()],
+        vec![
+            Node::new(
+                Shape::Mesh(0),
+                0,
+                Vec3::new(0.75, 0.75, 0.75),
+                Mat3::identity(),
+                Vec3::new(-1.5, 0., 7.),
+            ),
+            Node::new(
+                Shape::Mesh(0),
+                0,
+                Vec3::new(1., 1., 1.),
+                Quat::from_axis_angle(&Vec3::new(0., 1., 0.), 195.),
+                Vec3::new(1.25, 2.5, 7.5),
+            ),
+            Node::new(
+                Shape::Mesh(0),
+                0,
+                Vec3::new(1., 1., 1.),
+                Mat3::identity(),
+                Vec3::new(3., -1.5, 6.5),
+            ),
+        ],
+        vec![crate_texture],
+    )
+}
+
+/// The camera drifts to the right and back while the top crate turns a full circle.
+fn crates_timeline() -> Timeline {
+    let up = Vec3::new(0., 1., 0.);
+    Timeline::new(vec![
+        (
+            Target::Camera,
+            Channel::Translation(Track::new(
+                Interpolation::Cubic,
+                vec![
+                    (0., Vec3::new(-3., 1., 2.)),
+                    (2., Vec3::new(-2., 1.5, 1.)),
+                    (4., Vec3::new(-3., 1., 2.)),
+                ],
+            )),
+        ),
+        (
+            Target::Node(vec![1]),
+            Channel::Rotation(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0., Quat::from_axis_angle(&up, 195.)),
+                    (1., Quat::from_axis_angle(&up, 285.)),
+                    (2., Quat::from_axis_angle(&up, 15.)),
+                    (3., Quat::from_axis_angle(&up, 105.)),
+                    (4., Quat::from_axis_angle(&up, 195.)),
+                ],
+            )),
+        ),
+    ])
+}
+
+fn spheres() -> Scene {
+    let camera = Camera::new(
+        Vec3::new(3., 0., 1.),
+        Mat3::new_look_at(
+            &Vec3::new(3., 0., 1.),
+            &Vec3::new(0., 0., 4.),
+            &Vec3::new(0., 1., 0.),
+        ),
+        Projection::Perspective { fov: DEFAULT_FOV },
+        1.,
+        f64::INFINITY,
+    );
+
+    // Each sphere is the unit sphere scaled to its radius and moved to its center.
+    let sphere = |material: usize, center: Point, radius: f64| {
+        Node::new(
+            Shape::Sphere,
+            material,
+            Vec3::new(radius, radius, radius),
+            Mat3::identity(),
+            center,
+        )
+    };
+
+    Scene::new(
+        camera,
+        vec![
+            Light::Ambient {
+                intensity: Color::new(0.2, 0.2, 0.2),
+            },
+            Light::Point {
+                intensity: Color::new(0.6, 0.6, 0.6),
+                position: Vec3::new(2., 1., 0.),
+            },
+            Light::Directional {
+                intensity: Color::new(0.2, 0.2, 0.2),
+                direction: Vec3::new(1., 4., 4.),
+            },
+        ],
+        vec![
+            Material::new(
+                Color::new(255.0, 0.0, 0.0),
+                Scatter::Specular { shininess: 500. },
+                0.2,
+            ),
+            Material::new(
+                Color::new(0.0, 0.0, 255.0),
+                Scatter::Specular { shininess: 500. },
+                0.3,
+            ),
+            Material::new(
+                Color::new(0.0, 255.0, 0.0),
+                Scatter::Specular { shininess: 10. },
+                0.4,
+            ),
+            Material::new(
+                Color::new(255.0, 255.0, 0.0),
+                Scatter::Specular { shininess: 1000. },
+                0.5,
+            ),
+        ],
+        vec![],
+        vec![
+            sphere(0, Point::new(0.0, -1.0, 3.0), 1.0),
+            sphere(1, Point::new(2.0, 0.0, 4.0), 1.0),
+            sphere(2, Point::new(-2.0, 0.0, 4.0), 1.0),
+            sphere(3, Point::new(0., -5001.0, 0.0), 5000.),
+        ],
+        vec![],
+    )
+}
+
+/// The red sphere bounces while the point light circles overhead.
+fn spheres_timeline() -> Timeline {
+    Timeline::new(vec![
+        (
+            Target::Node(vec![0]),
+            Channel::Translation(Track::new(
+                Interpolation::Cubic,
+                vec![
+                    (0., Point::new(0., -1., 3.)),
+                    (0.5, Point::new(0., 0.5, 3.)),
+                    (1., Point::new(0., -1., 3.)),
+                ],
+            )),
+        ),
+        (
+            Target::Light(1),
+            Channel::Translation(Track::new(
+                Interpolation::Linear,
+                vec![
+                    (0., Vec3::new(2., 1., 0.)),
+                    (1., Vec3::new(0., 1., 2.)),
+                    (2., Vec3::new(-2., 1., 0.)),
+                    (3., Vec3::new(0., 1., -2.)),
+                    (4., Vec3::new(2., 1., 0.)),
+                ],
+            )),
+        ),
+    ])
+}
+
+fn materials() -> Scene {
+    const ROUGHNESSES: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+    let camera = Camera::new(
+        Vec3::new(0., 0., 0.),
+        Mat3::identity(),
+        Projection::Perspective { fov: DEFAULT_FOV },
+        1.,
+        100.,
+    );
+
+    // The gold spheres are in the top row and the plastic ones in the bottom row, getting rougher to the right.
+    let gold = Color::new(255., 195., 86.);
+    let plastic = Color::new(200., 30., 30.);
+    let mut materials = Vec::new();
+    let mut nodes = Vec::new();
+    for (row, &(color, metallic)) in [(gold, 1.), (plastic, 0.)].iter().enumerate() {
+        for (column, &roughness) in ROUGHNESSES.iter().enumerate() {
+            materials.push(Material::new(
+                color,
+                Scatter::MetallicRoughness {
+                    metallic,
+                    roughness,
+                },
+                0.,
+            ));
+            nodes.push(Node::new(
+                Shape::Sphere,
+                materials.len() - 1,
+                Vec3::new(0.45, 0.45, 0.45),
+                Mat3::identity(),
+                Vec3::new(column as f64 - 2., 0.6 - row as f64 * 1.2, 4.5),
+            ));
+        }
+    }
+
+    Scene::new(
+        camera,
+        vec![
+            Light::Ambient {
+                intensity: Color::new(0.08, 0.08, 0.1),
+            },
+            // A warm key light and a cool fill light.
+            Light::Point {
+                intensity: Color::new(0.8, 0.65, 0.45),
+                position: Vec3::new(-2., 2., 2.),
+            },
+            Light::Directional {
+                intensity: Color::new(0.15, 0.2, 0.3),
+                direction: Vec3::new(1., 1., -2.),
+            },
+        ],
+        materials,
+        vec![],
+        nodes,
+        vec![],
+    )
+}
+
+/// The point light sweeps from the left of the spheres to the right and back.
+fn materials_timeline() -> Timeline {
+    Timeline::new(vec![(
+        Target::Light(1),
+        Channel::Translation(Track::new(
+            Interpolation::Cubic,
+            vec![
+                (0., Vec3::new(-2., 2., 2.)),
+                (2., Vec3::new(2., 2., 2.)),
+                (4., Vec3::new(-2., 2., 2.)),
+            ],
+        )),
+    )])
+}
+
+/// A square of the xz plane from -1 to 1, facing up, with the texture's top along the far edge.
+fn floor() -> Mesh {
+    const UPPER: [(f64, f64); 3] = [(0., 0.), (1., 0.), (1., 1.)];
+    const LOWER: [(f64, f64); 3] = [(0., 0.), (1., 1.), (0., 1.)];
+    let up = Vec3::new(0., 1., 0.);
+    Mesh::new(
+        vec![
+            Vec3::new(-1., 0., 1.),
+            Vec3::new(1., 0., 1.),
+            Vec3::new(1., 0., -1.),
+            Vec3::new(-1., 0., -1.),
+        ],
+        vec![
+            Face::new([0, 1, 2], Some([up; 3]), UPPER),
+            Face::new([0, 2, 3], Some([up; 3]), LOWER),
+        ],
+    )
+}
+
+// The number of cobblestones along each side of a texture, how high they stand as a fraction of its width, and the
+// size of the textures in texels.
+const COBBLESTONES: f64 = 8.;
+const COBBLESTONE_DEPTH: f64 = 0.03;
+const COBBLESTONE_TEXTURE_SIZE: u32 = 256;
+
+/// The height of the cobblestones at (u, v), from 0 in the mortar between them to 1 in the middle of a stone. Every
+/// other row is offset by half a stone, like bricks.
+fn cobblestone_height(u: f64, v: f64) -> f64 {
+    let y = v * COBBLESTONES;
+    let x = u * COBBLESTONES + (y.floor() as i64 % 2) as f64 / 2.;
+
+    // The stones are squares rounded off into domes.
+    let (dx, dy) = (x.fract() * 2. - 1., y.fract() * 2. - 1.);
+    let r = (dx.powi(4) + dy.powi(4)).powf(0.25) / 0.85;
+    (1. - r * r).max(0.).sqrt()
+}
+
+fn cobblestone_texture() -> Texture {
+    let stone = Color::new(80., 72., 62.);
+    let mortar = Color::new(25., 23., 20.);
+    Texture::from_fn(
+        COBBLESTONE_TEXTURE_SIZE,
+        COBBLESTONE_TEXTURE_SIZE,
+        |u, v| {
+            if cobblestone_height(u, v) > 0. {
+                stone
+            } else {
+                mortar
+            }
+        },
+    )
+}
+
+fn cobblestone_height_map() -> Texture {
+    Texture::from_fn(
+        COBBLESTONE_TEXTURE_SIZE,
+        COBBLESTONE_TEXTURE_SIZE,
+        |u, v| {
+            let height = cobblestone_height(u, v) * 255.;
+            Color::new(height, height, height)
+        },
+    )
+}
+
+/// A normal map of the cobblestones, found from how steeply their height changes across the texture.
+fn cobblestone_normal_map() -> Texture {
+    let d = 1. / COBBLESTONE_TEXTURE_SIZE as f64;
+    Texture::from_normals(
+        COBBLESTONE_TEXTURE_SIZE,
+        COBBLESTONE_TEXTURE_SIZE,
+        |u, v| {
+            let du = (cobblestone_height(u + d, v) - cobblestone_height(u - d, v)) / (2. * d);
+            let dv = (cobblestone_height(u, v + d) - cobblestone_height(u, v - d)) / (2. * d);
+            // Up the texture is against v.
+            Vec3::new(-du * COBBLESTONE_DEPTH, dv * COBBLESTONE_DEPTH, 1.)
+        },
+    )
+}
+
+fn cobblestones() -> Scene {
+    let camera = Camera::new(
+        Vec3::new(0., 1.5, 0.),
+        Mat3::new_look_at(
+            &Vec3::new(0., 1.5, 0.),
+            &Vec3::new(0., -1., 4.),
+            &Vec3::new(0., 1., 0.),
+        ),
+        Projection::Perspective { fov: DEFAULT_FOV },
+        0.5,
+        100.,
+    );
+    let stone = Scatter::MetallicRoughness {
+        metallic: 0.,
+        roughness: 0.6,
+    };
+
+    Scene::new(
+        camera,
+        vec![
+            Light::Ambient {
+                intensity: Color::new(0.1, 0.1, 0.1),
+            },
+            Light::Point {
+                intensity: Color::new(1., 0.85, 0.65),
+                position: Vec3::new(-2., 0., 4.),
+            },
+            Light::Directional {
+                intensity: Color::new(0.1, 0.12, 0.16),
+                direction: Vec3::new(1., 2., -1.),
+            },
+        ],
+        vec![
+            Material::new_textured(0, stone, 0.)
+                .with_height_map(HeightMap::new(2, COBBLESTONE_DEPTH).with_displacement(48)),
+            Material::new_textured(0, stone, 0.).with_normal_map(1),
+        ],
+        vec![floor(), cube()],
+        vec![
+            Node::new(
+                Shape::Mesh(0),
+                0,
+                Vec3::new(3., 3., 3.),
+                Mat3::identity(),
+                Vec3::new(0., -1., 4.),
+            ),
+            Node::new(
+                Shape::Mesh(1),
+                1,
+                Vec3::new(0.6, 0.6, 0.6),
+                Quat::from_axis_angle(&Vec3::new(0., 1., 0.), 30.),
+                Vec3::new(0.5, -0.4, 4.5),
+            ),
+        ],
+        vec![
+            cobblestone_texture(),
+            cobblestone_normal_map(),
+            cobblestone_height_map(),
+        ],
+    )
+}
+
+/// The point light circles the block, low over the floor, where the relief of the stones shows the most.
+fn cobblestones_timeline() -> Timeline {
+    Timeline::new(vec![(
+        Target::Light(1),
+        Channel::Translation(Track::new(
+            Interpolation::Linear,
+            vec![
+                (0., Vec3::new(-2., 0., 4.)),
+                (1., Vec3::new(0.5, 0., 2.)),
+                (2., Vec3::new(3., 0., 4.5)),
+                (3., Vec3::new(0.5, 0., 7.)),
+                (4., Vec3::new(-2., 0., 4.)),
+            ],
+        )),
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::{Backend, Framebuffer};
+    use crate::scene::examples::Example;
+
+    #[test]
+    fn test_renderers_see_the_same_crates() {
+        let scene = Example::Crates.scene();
+        let mut rasterized = Framebuffer::new(120, 160);
+        let mut raytraced = Framebuffer::new(120, 160);
+        Backend::Rasterizer
+            .renderer()
+            .render(&scene, &mut rasterized);
+        Backend::Raytracer.renderer().render(&scene, &mut raytraced);
+
+        // The rasterizer leaves the background transparent and the raytracer makes it black, so compare which pixels
+        // each one covers. They only differ along the edges, where the rasterizer's pixels are partly covered.
+        let rasterized = rasterized.colors.chunks(4).map(|c| c[3] > 0.5);
+        let raytraced = raytraced.colors.chunks(4).map(|c| c[..3] != [0., 0., 0.]);
+        let covered = rasterized.clone().filter(|&covered| covered).count();
+        let differing = rasterized.zip(raytraced).filter(|(a, b)| a != b).count();
+        assert!(covered > 120 * 160 / 10);
+        assert!(differing < covered / 20, "{} of {}", differing, covered);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::scene::camera::Camera;
+use crate::scene::light::Light;
+use crate::scene::material::Material;
+use crate::scene::mesh::Mesh;
+use crate::scene::node::{Node, Shape};
+use crate::scene::texture::Texture;
+use crate::scene::Scene;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A scene as it's written in a JSON file. Everything but the textures is written as it's kept in the scene.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    camera: Camera,
+    lights: Vec<Light>,
+    materials: Vec<Material>,
+    #[serde(default)]
+    meshes: Vec<Mesh>,
+    nodes: Vec<Node>,
+    #[serde(default)]
+    textures: Vec<TextureFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TextureFile {
+    // The path of a JPEG, relative to the scene file.
+    path: PathBuf,
+    // Whether the texels are values that aren't colors, such as those of a normal or height map, which are loaded as
+    // they are rather than decoded from sRGB.
+    #[serde(default)]
+    linear: bool,
+}
+
+/// Loads a scene from a JSON file, along with the textures it names.
+pub fn load(path: &Path) -> Result<Scene> {
+    let json =
+        fs::read_to_string(path).with_context(|| format!("Couldn't read {}", path.display()))?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    from_json(&json, directory).with_context(|| format!("Couldn't load {}", path.display()))
+}
+
+/// Reads a scene from JSON, loading the textures it names from paths relative to `directory`.
+pub fn from_json(json: &str, directory: &Path) -> Result<Scene> {
+    let file: SceneFile = serde_json::from_str(json)?;
+    let textures = file
+        .textures
+        .iter()
+        .map(|texture| {
+            let path = directory.join(&texture.path);
+            let bytes =
+                fs::read(&path).with_context(|| format!("Couldn't read {}", path.display()))?;
+            let texture = if texture.linear {
+                Texture::from_linear_bytes(&bytes)
+            } else {
+                Texture::from_bytes(&bytes)
+            };
+            texture.with_context(|| format!("Couldn't decode {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let scene = Scene::new(
+        file.camera,
+        file.lights,
+        file.materials,
+        file.meshes,
+        file.nodes,
+        textures,
+    );
+    validate(&scene)?;
+    Ok(scene)
+}
+
+/// Checks that everything the scene refers to by its index is there, and that its meshes have faces and finite vertices,
+/// so the renderers don't have to.
+fn validate(scene: &Scene) -> Result<()> {
+    let check = |what: &str, index: usize, len: usize| {
+        if index >= len {
+            bail!("There's no {} {}, only {}", what, index, len);
+        }
+        Ok(())
+    };
+
+    for material in scene.materials().iter() {
+        let textures = [
+            material.texture,
+            material.normal_map,
+            material.height_map.map(|height_map| height_map.texture),
+        ];
+        for &texture in textures.iter().flatten() {
+            check("texture", texture, scene.textures().len())?;
+        }
+    }
+    for (m, mesh) in scene.meshes().iter().enumerate() {
+        if mesh.faces.is_empty() {
+            bail!("Mesh {} has no faces", m);
+        }
+        if let Some(v) = mesh.vertices.iter().position(|v| !v.is_finite()) {
+            bail!("Vertex {} of mesh {} isn't finite", v, m);
+        }
+        for face in mesh.faces.iter() {
+            for vertex in face.vertices {
+                check("vertex", vertex, mesh.vertices.len())?;
+            }
+        }
+    }
+
+    let mut nodes = scene.nodes.iter().collect::<Vec<_>>();
+    while let Some(node) = nodes.pop() {
+        if let Some((shape, material)) = node.shape {
+            check("material", material, scene.materials().len())?;
+            if let Shape::Mesh(mesh) = shape {
+                check("mesh", mesh, scene.meshes().len())?;
+            }
+        }
+        nodes.extend(node.children.iter());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rasterizer::main::Rasterizer;
+    use crate::raytracer::main::Raytracer;
+    use crate::renderer::{Framebuffer, Renderer};
+    use crate::scene::examples::Example;
+    use crate::scene::file;
+    use crate::scene::node::Shape;
+    use crate::vec3::Vec3;
+    use std::path::Path;
+
+    #[test]
+    fn test_load() {
+        // The example file is the spheres example, so both render the same.
+        let scene = file::load(Path::new("scenes/spheres.json")).unwrap();
+        let (mut loaded, mut built) = (Framebuffer::new(30, 40), Framebuffer::new(30, 40));
+        Raytracer::default().render(&scene, &mut loaded);
+        Raytracer::default().render(&Example::Spheres.scene(), &mut built);
+        assert_eq!(loaded.colors, built.colors);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = r#"{
+            "camera": {
+                "position": [0, 0, -3],
+                "orientation": {"yaw": 0},
+                "projection": {"Orthographic": {"height": 4}},
+                "near": 0.1
+            },
+            "lights": [{"Ambient": {"intensity": [1, 1, 1]}}],
+            "materials": [{"texture": 0, "scatter": "Diffuse"}],
+            "meshes": [{
+                "vertices": [[0, 0, 0], [1, 0, 0], [0, 1, 0]],
+                "faces": [{"vertices": [0, 2, 1], "normals": null, "uvs": [[0, 0], [1, 0], [0, 1]]}]
+            }],
+            "nodes": [{"children": [{"shape": [{"Mesh": 0}, 0], "translation": [0, 0, 1]}]}],
+            "textures": [{"path": "crate-texture.jpg"}]
+        }"#;
+        let scene = file::from_json(json, Path::new("src/scene")).unwrap();
+        assert_eq!(scene.camera.far, f64::INFINITY);
+        assert_eq!(scene.materials()[0].reflective, 0.);
+        let objects = scene.objects();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].shape, Shape::Mesh(0));
+
+        let error = |json: &str| {
+            file::from_json(json, Path::new("src/scene"))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        // Indexes past the end of what they index are caught when the scene is loaded.
+        let bad_material = json.replace(r#"[{"Mesh": 0}, 0]"#, r#"[{"Mesh": 0}, 1]"#);
+        assert_eq!(error(&bad_material), "There's no material 1, only 1");
+
+        // So are meshes with nothing to render, which have no bounds, and vertices that aren't numbers.
+        let no_faces = json.replace(
+            r#"[{"vertices": [0, 2, 1], "normals": null, "uvs": [[0, 0], [1, 0], [0, 1]]}]"#,
+            "[]",
+        );
+        assert_eq!(error(&no_faces), "Mesh 0 has no faces");
+        let mut scene = file::from_json(json, Path::new("src/scene")).unwrap();
+        scene.meshes_mut()[0].vertices[1] = Vec3::new(f64::INFINITY, 0., 0.);
+        assert_eq!(
+            file::validate(&scene).err().unwrap().to_string(),
+            "Vertex 1 of mesh 0 isn't finite"
+        );
+
+        // Texture coordinates outside the texture take the color, or the normal, at its edge.
+        let outside = json
+            .replace("[[0, 0], [1, 0], [0, 1]]", "[[-1, 0], [2, 0], [0, 3]]")
+            .replace(r#""texture": 0,"#, r#""texture": 0, "normal_map": 0,"#);
+        let scene = file::from_json(&outside, Path::new("src/scene")).unwrap();
+        let mut framebuffer = Framebuffer::new(20, 20);
+        Rasterizer::default().render(&scene, &mut framebuffer);
+        Raytracer::default().render(&scene, &mut framebuffer);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::animation::Channel;
+use crate::vec3::{Color, Mat4, Point, Vec3};
+use serde::Deserialize;
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+// The fraction of light a dielectric, such as plastic or wood, reflects when it's lit head on. glTF assumes every
+// dielectric has an index of refraction of 1.5, which gives 4%.
+const DIELECTRIC_REFLECTANCE: f64 = 0.04;
+
+// Roughnesses below this make the highlights of point and directional lights too small to hit any pixel.
+const MIN_ROUGHNESS: f64 = 0.03;
+
+/// A source of light. Its intensity is given for each of red, green and blue, where 1 lights a surface facing it with
+/// the surface's full color, so a light can be tinted.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Light {
+    // Simulates the light being scattered by other objects without actually computing it.
+    Ambient { intensity: Color },
+
+    // Light emitting from a single point.
+    Point { intensity: Color, position: Point },
+
+    // Simulates the Sun, where the source of the light is so far away affectively all light is coming from the same
+    // direction.
+    Directional { intensity: Color, direction: Vec3 },
+}
+
+impl Light {
+    /// Sets the light's intensity, position or direction to its value at `time` in the channel's track.
+    pub fn animate(&mut self, channel: &Channel, time: f64) {
+        match (self, channel) {
+            (
+                Light::Ambient { intensity }
+                | Light::Point { intensity, .. }
+                | Light::Directional { intensity, .. },
+                Channel::Intensity(track),
+            ) => *intensity = track.sample(time),
+            (Light::Point { position, .. }, Channel::Translation(track)) => {
+                *position = track.sample(time)
+            }
+            (Light::Directional { direction, .. }, Channel::Direction(track)) => {
+                *direction = track.sample(time)
+            }
+            _ => {}
+        }
+    }
+
+    /// The light moved into another space, such as the camera's. Directions are turned but not moved.
+    pub fn transformed(&self, transformation: &Mat4) -> Light {
+        match *self {
+            Light::Ambient { intensity } => Light::Ambient { intensity },
+            Light::Point {
+                intensity,
+                position,
+            } => Light::Point {
+                intensity,
+                position: (transformation * position.to_vec4(1.0)).to_vec3(),
+            },
+            Light::Directional {
+                intensity,
+                direction,
+            } => Light::Directional {
+                intensity,
+                direction: (transformation * direction.to_vec4(0.0)).to_vec3(),
+            },
+        }
+    }
+}
+
+/// The light reflected from a point on a surface towards the viewer, in each of red, green and blue, split by which of
+/// the surface's colors filters it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reflection {
+    // Light that's filtered by the surface's color: the light it scatters, and the light a metal reflects.
+    pub diffuse: Color,
+
+    // Light that's filtered by the surface's specular color, such as the highlights on plastic.
+    pub specular: Color,
+}
+
+impl Reflection {
+    pub fn new(diffuse: Color, specular: Color) -> Reflection {
+        Reflection { diffuse, specular }
+    }
+
+    /// The color of the reflected light off a surface with the given diffuse and specular colors.
+    pub fn shade(&self, color: Color, specular: Color) -> Color {
+        self.diffuse.component_mul(&color) + self.specular.component_mul(&specular)
+    }
+}
+
+impl Default for Reflection {
+    fn default() -> Reflection {
+        Reflection::new(Color::new(0., 0., 0.), Color::new(0., 0., 0.))
+    }
+}
+
+impl Add<Reflection> for Reflection {
+    type Output = Reflection;
+
+    fn add(self, rhs: Reflection) -> Self::Output {
+        Reflection::new(self.diffuse + rhs.diffuse, self.specular + rhs.specular)
+    }
+}
+
+impl Sub<Reflection> for Reflection {
+    type Output = Reflection;
+
+    fn sub(self, rhs: Reflection) -> Self::Output {
+        Reflection::new(self.diffuse - &rhs.diffuse, self.specular - &rhs.specular)
+    }
+}
+
+impl Mul<f64> for Reflection {
+    type Output = Reflection;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Reflection::new(self.diffuse * rhs, self.specular * rhs)
+    }
+}
+
+impl Div<f64> for Reflection {
+    type Output = Reflection;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Reflection::new(self.diffuse / rhs, self.specular / rhs)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Scatter {
+    // Diffuse: A matte reflection. Surface is irregular and so light is scattered equally in every direction.
+    Diffuse,
+
+    // Specular: A shiny reflection. Surface is even so light is scattered in a single (or close to a single)
+    // direction, the vector R. The greater the shininess the lower the reflected light as you move away from R.
+    Specular { shininess: f64 },
+
+    // BlinnPhong: Like Specular, but the highlight falls off with the angle between the normal and H, the vector
+    // halfway between the light and the viewer. Unlike R, H stays meaningful at grazing angles, so highlights don't
+    // get cut off. It takes about four times the shininess to give a highlight as small as Specular's.
+    BlinnPhong { shininess: f64 },
+
+    // MetallicRoughness: glTF's physically based model, a Cook-Torrance BRDF with a GGX distribution of microfacets.
+    // Metallic goes from 0 for a dielectric, which scatters its color and reflects highlights in its specular color,
+    // to 1 for a metal, which only reflects, tinted by its color. Roughness goes from 0 for a mirror to 1 for a matte
+    // surface. Both mix between the extremes, and neither reflects more light than reaches it.
+    MetallicRoughness { metallic: f64, roughness: f64 },
+}
+
+impl Scatter {
+    /// Calculates the light being reflected from a point on a surface towards the viewer.
+    ///
+    /// # Arguments
+    ///
+    /// * `lights`: the light sources, in the same space as the point.
+    /// * `point`: the point on the surface.
+    /// * `normal`: the surface normal at the point.
+    /// * `view`: the vector from the point towards the viewer.
+    /// * `in_shadow`: whether something blocks the light coming from the given direction. The light is `t_max` times
+    ///   the direction away, so a point light is exactly at the end of it.
+    ///
+    /// returns: Reflection the intensity of the reflection, which is determined by how the material scatters the
+    /// light from the light sources and the angle at which the light hits the surface.
+    ///
+    pub fn intensity(
+        &self,
+        lights: &[Light],
+        point: &Point,
+        normal: &Vec3,
+        view: &Vec3,
+        mut in_shadow: impl FnMut(&Vec3, f64) -> bool,
+    ) -> Reflection {
+        let mut res = Reflection::default();
+        for light in lights {
+            if let &Light::Ambient { intensity } = light {
+                res.diffuse = res.diffuse + intensity
+            } else {
+                let (intensity, l, t_max) = match light {
+                    Light::Point {
+                        intensity,
+                        position,
+                    } => (*intensity, position - point, 1.0),
+                    Light::Directional {
+                        intensity,
+                        direction,
+                    } => (*intensity, *direction, f64::INFINITY),
+                    _ => panic!("Can't happen"),
+                };
+
+                if !in_shadow(&l, t_max) {
+                    let (diffuse, specular) = self.reflect(view, &l, normal);
+                    res = res + Reflection::new(intensity * diffuse, intensity * specular)
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns the fractions of light that are scattered and reflected as highlights, as a function of the angle
+    /// between the surface normal and the direction of the light.
+    ///
+    fn reflect(&self, view: &Vec3, light: &Vec3, normal: &Vec3) -> (f64, f64) {
+        if let &Scatter::MetallicRoughness {
+            metallic,
+            roughness,
+        } = self
+        {
+            return metallic_roughness(metallic, roughness, view, light, normal);
+        }
+
+        let n_dot_l = normal.dot(&light);
+        let diffuse = if n_dot_l > 0. {
+            // We're computing intensity / area, which is the equivalent to the cosine of the angle between the light
+            // (l) and the surface normal. That is equal to <l, n> / |n||l|
+            n_dot_l / (normal.len() * light.len())
+        } else {
+            0.
+        };
+
+        let mut specular = 0.;
+        match *self {
+            Scatter::Specular { shininess } => {
+                // r is the light reflected from the surface normal.
+                let reflected = light.reflect(normal);
+                let r_dot_v = reflected.dot(view);
+                if r_dot_v > 0. {
+                    // The cosine of the angle between r and v, which is the fraction of light reflected back at v.
+                    // The less shiny the object, the quicker that intensity decreases as the angle increases.
+                    specular = (r_dot_v / (reflected.len() * view.len())).powf(shininess);
+                }
+            }
+            Scatter::BlinnPhong { shininess } => {
+                // h is halfway between the light and the viewer, so it lines up with the normal exactly when r lines
+                // up with v. Only surfaces facing the light have a highlight.
+                let halfway = light / light.len() + view / view.len();
+                let n_dot_h = normal.dot(&halfway);
+                if n_dot_l > 0. && n_dot_h > 0. {
+                    specular = (n_dot_h / (normal.len() * halfway.len())).powf(shininess);
+                }
+            }
+            _ => {}
+        }
+
+        (diffuse, specular)
+    }
+}
+
+/// The fractions of light reflected by glTF's metallic-roughness BRDF that are filtered by the surface's color and by
+/// its specular color, scaled by pi so that a white dielectric lit head on reflects about as much as
+/// `Scatter::Diffuse` does.
+///
+/// The reflections off the microfacets follow the GGX distribution, are shadowed by Smith's height-correlated
+/// visibility term and are weighted by Schlick's approximation of Fresnel. A dielectric scatters the light its
+/// highlight doesn't reflect, while a metal reflects its color, tending to its specular color at grazing angles.
+fn metallic_roughness(
+    metallic: f64,
+    roughness: f64,
+    view: &Vec3,
+    light: &Vec3,
+    normal: &Vec3,
+) -> (f64, f64) {
+    let n = normal / normal.len();
+    let l = light / light.len();
+    let v = view / view.len();
+    let n_dot_l = n.dot(&l);
+    if n_dot_l <= 0. {
+        return (0., 0.);
+    }
+    // Normals that are interpolated or turned to face the ray can point slightly away from the viewer.
+    let n_dot_v = n.dot(&v).max(1e-4);
+    let h = l + v;
+    let h = h / h.len();
+    let n_dot_h = n.dot(&h).max(0.);
+    let v_dot_h = v.dot(&h).max(0.);
+
+    let metallic = metallic.clamp(0., 1.);
+    let alpha = roughness.clamp(MIN_ROUGHNESS, 1.).powi(2);
+    let alpha2 = alpha * alpha;
+    let distribution = alpha2 / (PI * (n_dot_h * n_dot_h * (alpha2 - 1.) + 1.).powi(2));
+    let visibility = 0.5
+        / (n_dot_l * (n_dot_v * n_dot_v * (1. - alpha2) + alpha2).sqrt()
+            + n_dot_v * (n_dot_l * n_dot_l * (1. - alpha2) + alpha2).sqrt());
+    let specular = PI * distribution * visibility * n_dot_l;
+
+    // Schlick's approximation, F = F0 + (1 - F0) * fresnel, where F0 is the reflectance head on.
+    let fresnel = (1. - v_dot_h).powi(5);
+    let dielectric = DIELECTRIC_REFLECTANCE + (1. - DIELECTRIC_REFLECTANCE) * fresnel;
+
+    // A metal's F0 is its color, so the part of its reflection that's F0 * (1 - fresnel) is tinted.
+    (
+        (1. - metallic) * (1. - dielectric) * n_dot_l + metallic * (1. - fresnel) * specular,
+        (1. - metallic) * dielectric * specular + metallic * fresnel * specular,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scene::light::{Light, Reflection, Scatter};
+    use crate::vec3::{Color, Point, Vec3};
+
+    /// The reflection off a surface facing up of a white light, with an intensity of 1.
+    fn reflection(scatter: Scatter, light: Vec3, view: Vec3) -> Reflection {
+        let lights = vec![Light::Directional {
+            intensity: Color::new(1., 1., 1.),
+            direction: light,
+        }];
+        let normal = Vec3::new(0., 1., 0.);
+        scatter.intensity(&lights, &Point::new(0., 0., 0.), &normal, &view, |_, _| {
+            false
+        })
+    }
+
+    #[test]
+    fn test_colored_lights() {
+        // Each channel of the light is reflected separately.
+        let up = Vec3::new(0., 1., 0.);
+        let lights = vec![
+            Light::Ambient {
+                intensity: Color::new(0., 0., 0.2),
+            },
+            Light::Directional {
+                intensity: Color::new(1., 0.5, 0.),
+                direction: up,
+            },
+        ];
+        let scatter = Scatter::Specular { shininess: 10. };
+        let res = scatter.intensity(&lights, &Point::new(0., 0., 0.), &up, &up, |_, _| false);
+        assert_eq!(res.diffuse, Color::new(1., 0.5, 0.2));
+        assert_eq!(res.specular, Color::new(1., 0.5, 0.));
+
+        let red = Color::new(255., 0., 0.);
+        let white = Color::new(255., 255., 255.);
+        assert_eq!(res.shade(red, white), Color::new(510., 127.5, 0.));
+    }
+
+    #[test]
+    fn test_blinn_phong_highlights() {
+        // Both highlights peak when the viewer looks along the reflection of the light, and Blinn-Phong's is wider
+        // for the same shininess.
+        let light = Vec3::new(1., 1., 0.);
+        let mirrored = Vec3::new(-1., 1., 0.);
+        let off = Vec3::new(-1., 2., 0.);
+        let phong = Scatter::Specular { shininess: 50. };
+        let blinn_phong = Scatter::BlinnPhong { shininess: 50. };
+        for scatter in [phong, blinn_phong] {
+            assert!((reflection(scatter, light, mirrored).specular[0] - 1.).abs() < 1e-9);
+        }
+        assert!(
+            reflection(blinn_phong, light, off).specular[0]
+                > reflection(phong, light, off).specular[0]
+        );
+
+        // At a grazing angle Phong's highlight is cut off while Blinn-Phong's isn't.
+        let grazing = Vec3::new(1., 0.05, 0.);
+        let behind = Vec3::new(1., 0.2, 1.);
+        let phong = Scatter::Specular { shininess: 2. };
+        let blinn_phong = Scatter::BlinnPhong { shininess: 2. };
+        assert_eq!(reflection(phong, grazing, behind).specular[0], 0.);
+        assert!(reflection(blinn_phong, grazing, behind).specular[0] > 0.);
+    }
+
+    #[test]
+    fn test_metallic_roughness_conserves_energy() {
+        // Even at its brightest, a rough white surface lit head on reflects no more than a diffuse one.
+        let up = Vec3::new(0., 1., 0.);
+        let diffuse = reflection(Scatter::Diffuse, up, up);
+        for metallic in [0., 0.5, 1.] {
+            let rough = Scatter::MetallicRoughness {
+                metallic,
+                roughness: 1.,
+            };
+            let res = reflection(rough, up, up);
+            assert!(
+                res.diffuse[0] + res.specular[0] <= diffuse.diffuse[0] * 1.01,
+                "{:?}",
+                res
+            );
+        }
+    }
+
+    #[test]
+    fn test_metallic_roughness_highlights() {
+        // Only a metal tints its highlight. Looking along the reflection of the light, a dielectric's highlight takes
+        // on its specular color and a metal's takes on its own.
+        let light = Vec3::new(1., 1., 0.);
+        let view = Vec3::new(-1., 1., 0.);
+        let scatter = |metallic| Scatter::MetallicRoughness {
+            metallic,
+            roughness: 0.2,
+        };
+        let dielectric = reflection(scatter(0.), light, view);
+        let metal = reflection(scatter(1.), light, view);
+        assert!(dielectric.specular[0] > 1.);
+        assert!(dielectric.diffuse[0] < 1.);
+        assert!(metal.diffuse[0] > 10. * metal.specular[0]);
+
+        // Facing away from the light, nothing is reflected.
+        let behind = reflection(scatter(0.), -light, view);
+        assert_eq!(behind, Reflection::default());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::scene::light::Scatter;
+use crate::vec3::Color;
+use serde::Deserialize;
+
+/// A texture of heights over a surface, which bumps its normals where it rises and falls, from black at the surface to
+/// white `depth` over it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct HeightMap {
+    // The index of the height map in the scene's textures.
+    pub texture: usize,
+    // How high white stands over the surface, as a fraction of the width of the texture on it.
+    pub depth: f64,
+    // The number of pieces the rasterizer splits each side of a mesh's triangles into before moving their vertices
+    // out by the height map, which gives the surface a real silhouette. With 1 it's only bumped. The raytracer only
+    // ever bumps it.
+    #[serde(default = "HeightMap::no_displacement")]
+    pub divisions: usize,
+}
+
+impl HeightMap {
+    pub fn new(texture: usize, depth: f64) -> HeightMap {
+        HeightMap {
+            texture,
+            depth,
+            divisions: 1,
+        }
+    }
+
+    fn no_displacement() -> usize {
+        1
+    }
+
+    /// The height map with the rasterizer displacing meshes by it, splitting each side of their triangles into
+    /// `divisions` pieces.
+    pub fn with_displacement(mut self, divisions: usize) -> HeightMap {
+        self.divisions = divisions.max(1);
+        self
+    }
+}
+
+/// How a surface looks: its colors, how it scatters light and how much of the scene it reflects. Scene files can leave
+/// out its colors, which are then white, and its textures and reflectiveness, which it then doesn't have.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Material {
+    #[serde(default = "Material::white")]
+    pub color: Color,
+    // The index of a texture in the scene's textures. When there is one, the surface's color is read from it at the
+    // mesh's texture coordinates instead.
+    pub texture: Option<usize>,
+    // The index of a normal map in the scene's textures, which tilts the surface's normals at the mesh's texture
+    // coordinates to add detail its triangles don't have. Spheres have no texture coordinates, so they ignore it, and
+    // the rasterizer only reads it when it shades each pixel, with Phong shading.
+    pub normal_map: Option<usize>,
+    // Bumps the surface's normals like the normal map does, unless there is one, in which case it only displaces it.
+    pub height_map: Option<HeightMap>,
+    // The color of the surface's highlights, white unless it's given. A metallic surface's highlights take on its
+    // color instead.
+    #[serde(default = "Material::white")]
+    pub specular: Color,
+    pub scatter: Scatter,
+    // The fraction of the color that's reflected from the rest of the scene, from 0 to 1. Only the raytracer renders
+    // reflections.
+    #[serde(default)]
+    pub reflective: f64,
+}
+
+impl Material {
+    pub fn new(color: Color, scatter: Scatter, reflective: f64) -> Material {
+        Material {
+            color,
+            texture: None,
+            normal_map: None,
+            height_map: None,
+            specular: Color::new(255., 255., 255.),
+            scatter,
+            reflective,
+        }
+    }
+
+    pub fn new_textured(texture: usize, scatter: Scatter, reflective: f64) -> Material {
+        Material {
+            color: Color::new(255., 255., 255.),
+            texture: Some(texture),
+            normal_map: None,
+            height_map: None,
+            specular: Color::new(255., 255., 255.),
+            scatter,
+            reflective,
+        }
+    }
+
+    fn white() -> Color {
+        Color::new(255., 255., 255.)
+    }
+
+    /// The material with highlights of the given color.
+    pub fn with_specular(mut self, specular: Color) -> Material {
+        self.specular = specular;
+        self
+    }
+
+    /// The material with its normals read from the normal map with the given index in the scene's textures.
+    pub fn with_normal_map(mut self, normal_map: usize) -> Material {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// The material with its normals bumped by the height map.
+    pub fn with_height_map(mut self, height_map: HeightMap) -> Material {
+        self.height_map = Some(height_map);
+        self
+    }
+
+    /// The index of the texture that tilts the surface's normals, and its depth when it's a height map rather than a
+    /// normal map.
+    pub fn bump(&self) -> Option<(usize, Option<f64>)> {
+        match (self.normal_map, self.height_map) {
+            (Some(normal_map), _) => Some((normal_map, None)),
+            (None, Some(height_map)) => Some((height_map.texture, Some(height_map.depth))),
+            (None, None) => None,
+        }
+    }
+}
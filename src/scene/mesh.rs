@@ -0,0 +1,341 @@
+use crate::vec3::{Mat3, Vec3};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A triangle of a mesh.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Face {
+    // The indexes of the corners in the mesh's vertices, counterclockwise when seen from the front.
+    pub vertices: [usize; 3],
+    // The normals at the corners. Without them the face is shaded flat.
+    pub normals: Option<[Vec3; 3]>,
+    // The texture coordinates of the corners, which scene files can leave out for untextured meshes.
+    #[serde(default)]
+    pub uvs: [(f64, f64); 3],
+}
+
+impl Face {
+    pub fn new(vertices: [usize; 3], normals: Option<[Vec3; 3]>, uvs: [(f64, f64); 3]) -> Face {
+        Face {
+            vertices,
+            normals,
+            uvs,
+        }
+    }
+}
+
+/// The directions along a surface in which its texture coordinates increase, which orient a normal map on it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TangentFrame {
+    // The direction in which u increases.
+    pub tangent: Vec3,
+    // The direction in which v increases, which is down the texture.
+    pub bitangent: Vec3,
+}
+
+impl TangentFrame {
+    pub fn new(tangent: Vec3, bitangent: Vec3) -> TangentFrame {
+        TangentFrame { tangent, bitangent }
+    }
+
+    /// The frame of a triangle from its texture coordinates, with the tangent and bitangent as long as the distance
+    /// along the triangle over which u and v increase by 1. There's none when they don't change across it.
+    pub fn of_triangle(vertices: [&Vec3; 3], uvs: [(f64, f64); 3]) -> Option<TangentFrame> {
+        let [p0, p1, p2] = vertices;
+        let [uv0, uv1, uv2] = uvs;
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+        let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+        let r = du1 * dv2 - du2 * dv1;
+        if r.abs() < 1e-12 {
+            return None;
+        }
+        Some(TangentFrame {
+            tangent: (e1 * dv2 - &(e2 * dv1)) / r,
+            bitangent: (e2 * du1 - &(e1 * du2)) / r,
+        })
+    }
+
+    /// The frame at the point with the given barycentric `weights` in a triangle with these frames at its corners.
+    pub fn interpolate(frames: &[TangentFrame; 3], weights: [f64; 3]) -> TangentFrame {
+        TangentFrame {
+            tangent: frames[0].tangent * weights[0]
+                + frames[1].tangent * weights[1]
+                + frames[2].tangent * weights[2],
+            bitangent: frames[0].bitangent * weights[0]
+                + frames[1].bitangent * weights[1]
+                + frames[2].bitangent * weights[2],
+        }
+    }
+
+    /// The frame moved into another space, such as the world's. Tangents lie along the surface, so unlike normals
+    /// they're transformed like the surface itself.
+    pub fn transformed(&self, transformation: &Mat3) -> TangentFrame {
+        TangentFrame {
+            tangent: transformation * self.tangent,
+            bitangent: transformation * self.bitangent,
+        }
+    }
+
+    /// Turns a normal read from a normal map, where x is along u, y is up the texture and z is out of the surface,
+    /// into the space of the frame and the surface's `normal`. The frame is first made perpendicular to the normal,
+    /// so interpolated frames still work.
+    pub fn perturb(&self, normal: &Vec3, mapped: &Vec3) -> Vec3 {
+        let normal = normal / normal.len();
+        let tangent = self.tangent - &(normal * normal.dot(&self.tangent));
+        if tangent.len() < 1e-12 {
+            // The texture coordinates don't increase along the surface, so there's nothing to orient the map by.
+            return normal;
+        }
+        let tangent = tangent / tangent.len();
+
+        // Mirrored texture coordinates flip the bitangent, and so the direction up the texture.
+        let mut up = normal.cross_product(&tangent);
+        if up.dot(&self.bitangent) > 0. {
+            up = -up;
+        }
+        let perturbed = tangent * mapped[0] + up * mapped[1] + normal * mapped[2];
+        perturbed / perturbed.len()
+    }
+}
+
+/// A surface made of triangles, in its own space.
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Mesh {
+    pub vertices: Vec<Vec3>,
+    pub faces: Vec<Face>,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Vec3>, faces: Vec<Face>) -> Mesh {
+        Mesh { vertices, faces }
+    }
+
+    /// Replaces the normals of the faces with smooth ones, as `smooth_normals` finds them.
+    pub fn with_smooth_normals(mut self, crease_angle: f64) -> Mesh {
+        let normals = smooth_normals(
+            &self.vertices,
+            &self.faces,
+            |face| face.vertices,
+            crease_angle,
+        );
+        for (face, normals) in self.faces.iter_mut().zip(normals) {
+            face.normals = Some(normals);
+        }
+        self
+    }
+
+    /// The tangent frames at the corners of each face, found from how the texture coordinates change across the face.
+    /// Corners that share a vertex and a normal are smoothed together, so normal maps shade smoothly across curved
+    /// surfaces while hard edges, such as those of a cube, keep the frames of their own faces.
+    pub fn tangent_frames(&self) -> Vec<[TangentFrame; 3]> {
+        let face_frames = self
+            .faces
+            .iter()
+            .map(|face| {
+                match TangentFrame::of_triangle(face.vertices.map(|v| &self.vertices[v]), face.uvs)
+                {
+                    // Each face counts the same however much of the texture it covers.
+                    Some(TangentFrame { tangent, bitangent }) => {
+                        TangentFrame::new(tangent / tangent.len(), bitangent / bitangent.len())
+                    }
+                    None => TangentFrame::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut corners_by_vertex = vec![Vec::new(); self.vertices.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for (corner, &vertex) in face.vertices.iter().enumerate() {
+                corners_by_vertex[vertex].push((f, corner));
+            }
+        }
+
+        self.faces
+            .iter()
+            .enumerate()
+            .map(|(f, face)| {
+                [0, 1, 2].map(|corner| match face.normals {
+                    None => face_frames[f],
+                    Some(normals) => corners_by_vertex[face.vertices[corner]]
+                        .iter()
+                        .filter(|&&(other, other_corner)| {
+                            self.faces[other].normals.map(|n| n[other_corner])
+                                == Some(normals[corner])
+                        })
+                        .map(|&(other, _)| face_frames[other])
+                        .fold(
+                            TangentFrame::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.)),
+                            |sum, frame| {
+                                TangentFrame::new(
+                                    sum.tangent + frame.tangent,
+                                    sum.bitangent + frame.bitangent,
+                                )
+                            },
+                        ),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The normals at the corners of triangles, whose corners `corners` gives as indexes into the vertices, found by
+/// averaging the normals of the triangles around each corner. Each triangle counts by its area and by its angle at the
+/// corner, so how a surface happens to be split into triangles barely changes its normals. Triangles whose normals are
+/// more than `crease_angle` degrees apart aren't averaged together, so hard edges stay hard. Vertices at the same
+/// position are treated as one, as meshes often split them where their texture coordinates change.
+pub fn smooth_normals<T>(
+    vertices: &[Vec3],
+    triangles: &[T],
+    corners: impl Fn(&T) -> [usize; 3],
+    crease_angle: f64,
+) -> Vec<[Vec3; 3]> {
+    let mut positions = HashMap::new();
+    let position_of: Vec<usize> = vertices
+        .iter()
+        .map(|v| {
+            // Adding 0 turns -0 into 0, so both land on the same position.
+            let key = [v[0] + 0., v[1] + 0., v[2] + 0.].map(f64::to_bits);
+            let next = positions.len();
+            *positions.entry(key).or_insert(next)
+        })
+        .collect();
+
+    let mut corners_by_position = vec![Vec::new(); positions.len()];
+    for (t, triangle) in triangles.iter().enumerate() {
+        for (corner, &vertex) in corners(triangle).iter().enumerate() {
+            corners_by_position[position_of[vertex]].push((t, corner));
+        }
+    }
+
+    // As long as twice the triangle's area.
+    let face_normals: Vec<Vec3> = triangles
+        .iter()
+        .map(|triangle| {
+            let [v0, v1, v2] = corners(triangle).map(|v| &vertices[v]);
+            (v1 - v0).cross_product(&(v2 - v0))
+        })
+        .collect();
+    let corner_angle = |t: usize, corner: usize| {
+        let indices = corners(&triangles[t]);
+        let [v0, v1, v2] = [0, 1, 2].map(|k| &vertices[indices[(corner + k) % 3]]);
+        let (e1, e2) = (v1 - v0, v2 - v0);
+        let lengths = e1.len() * e2.len();
+        if lengths == 0. {
+            0.
+        } else {
+            (e1.dot(&e2) / lengths).clamp(-1., 1.).acos()
+        }
+    };
+    let min_cos = crease_angle.to_radians().cos();
+
+    triangles
+        .iter()
+        .enumerate()
+        .map(|(t, triangle)| {
+            let own = face_normals[t];
+            let own_len = own.len();
+            corners(triangle).map(|vertex| {
+                let normal = corners_by_position[position_of[vertex]]
+                    .iter()
+                    .filter(|&&(other, _)| {
+                        // A triangle without an area has no normal of its own, so it takes its neighbours'.
+                        let n = &face_normals[other];
+                        own_len == 0. || n.dot(&own) >= min_cos * n.len() * own_len
+                    })
+                    .fold(Vec3::new(0., 0., 0.), |sum, &(other, other_corner)| {
+                        sum + face_normals[other] * corner_angle(other, other_corner)
+                    });
+                if normal.len() > 0. {
+                    normal / normal.len()
+                } else {
+                    normal
+                }
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scene::mesh::{Face, Mesh};
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn test_tangent_frames() {
+        // A square facing up, with u increasing along x and v towards -z.
+        let up = Vec3::new(0., 1., 0.);
+        let mesh = Mesh::new(
+            vec![
+                Vec3::new(-1., 0., 1.),
+                Vec3::new(1., 0., 1.),
+                Vec3::new(1., 0., -1.),
+                Vec3::new(-1., 0., -1.),
+            ],
+            vec![
+                Face::new([0, 1, 2], Some([up; 3]), [(0., 0.), (1., 0.), (1., 1.)]),
+                Face::new([0, 2, 3], None, [(0., 0.), (1., 1.), (0., 1.)]),
+            ],
+        );
+        for frame in mesh.tangent_frames().iter().flatten() {
+            assert!((&frame.tangent - &Vec3::new(1., 0., 0.)).len() < 1e-9);
+            assert!((&frame.bitangent - &Vec3::new(0., 0., -1.)).len() < 1e-9);
+        }
+
+        // A flat normal map leaves the normal as it is, and one tilted along u and up the texture tilts it towards x
+        // and z.
+        let frame = mesh.tangent_frames()[0][0];
+        assert_eq!(frame.perturb(&up, &Vec3::new(0., 0., 1.)), up);
+        let tilted = frame.perturb(&(&up * 2.), &Vec3::new(1., 1., 1.));
+        let expected = Vec3::new(1., 1., 1.) / 3f64.sqrt();
+        assert!((&tilted - &expected).len() < 1e-9);
+    }
+
+    #[test]
+    fn test_smooth_normals() {
+        // A cube whose faces aren't all split along diagonals through the same corners, with its top face on copies of
+        // its vertices, as if they had texture coordinates of their own.
+        let mut vertices = Vec::new();
+        for i in 0..8 {
+            let [x, y, z] = [1, 2, 4].map(|bit| if i & bit == 0 { -1. } else { 1. });
+            vertices.push(Vec3::new(x, y, z));
+        }
+        vertices.extend([2, 3, 6, 7].map(|i| vertices[i]));
+        let no_uvs = [(0., 0.); 3];
+        let faces = [
+            [0, 2, 3],
+            [0, 3, 1],
+            [4, 5, 7],
+            [4, 7, 6],
+            [0, 4, 6],
+            [0, 6, 2],
+            [1, 3, 7],
+            [1, 7, 5],
+            [0, 1, 5],
+            [0, 5, 4],
+            [8, 10, 11],
+            [8, 11, 9],
+        ]
+        .map(|vertices| Face::new(vertices, None, no_uvs));
+        let cube = || Mesh::new(vertices.clone(), faces.to_vec());
+
+        // Below the crease angle each face stays flat.
+        let flat = cube().with_smooth_normals(60.);
+        for face in flat.faces.iter() {
+            let [v0, v1, v2] = face.vertices.map(|v| flat.vertices[v]);
+            let normal = (v1 - &v0).cross_product(&(v2 - &v0));
+            for n in face.normals.unwrap() {
+                assert!((&n - &(normal / normal.len())).len() < 1e-9);
+            }
+        }
+
+        // Above it each corner points away from the center, however its faces are split.
+        let smooth = cube().with_smooth_normals(100.);
+        for face in smooth.faces.iter() {
+            for (n, v) in face.normals.unwrap().iter().zip(face.vertices) {
+                let expected = smooth.vertices[v] / 3f64.sqrt();
+                assert!((n - &expected).len() < 1e-9, "{:?} {:?}", n, expected);
+            }
+        }
+    }
+}
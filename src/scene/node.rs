@@ -0,0 +1,164 @@
+use crate::vec3::{Mat3, Mat4, Vec3};
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Deserialize)]
+pub enum Shape {
+    // The index of a mesh in the scene's meshes.
+    Mesh(usize),
+
+    // A sphere of radius 1 around the node's origin. The raytracer intersects it exactly, and the rasterizer
+    // tessellates it. Textures aren't mapped onto spheres, and scaling one unevenly is treated as scaling it by the
+    // largest of the scales.
+    Sphere,
+}
+
+/// A shape placed in the world, with the material it's made of.
+pub struct Object {
+    pub shape: Shape,
+    // The index of the material in the scene's materials.
+    pub material: usize,
+    pub transformation: Mat4,
+}
+
+/// A node in the scene graph. Its scale, rotation and translation are relative to its parent, so moving a node moves
+/// all of its children with it. Scene files can leave out any of its fields, which then don't change anything.
+#[derive(Deserialize)]
+pub struct Node {
+    // The shape and the index of its material in the scene's materials.
+    #[serde(default)]
+    pub shape: Option<(Shape, usize)>,
+    #[serde(default = "Node::no_scale")]
+    pub scale: Vec3,
+    #[serde(default = "Mat3::identity")]
+    pub rotation: Mat3,
+    #[serde(default = "Node::no_translation")]
+    pub translation: Vec3,
+    #[serde(default)]
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// Creates a node that renders a shape. The rotation can be given as a `Mat3` or a `Quat`.
+    pub fn new(
+        shape: Shape,
+        material: usize,
+        scale: Vec3,
+        rotation: impl Into<Mat3>,
+        translation: Vec3,
+    ) -> Node {
+        Node {
+            shape: Some((shape, material)),
+            scale,
+            rotation: rotation.into(),
+            translation,
+            children: Vec::new(),
+        }
+    }
+
+    /// Creates a node with nothing to render of its own, to position its children as one.
+    pub fn new_group(scale: Vec3, rotation: impl Into<Mat3>, translation: Vec3) -> Node {
+        Node {
+            shape: None,
+            scale,
+            rotation: rotation.into(),
+            translation,
+            children: Vec::new(),
+        }
+    }
+
+    fn no_scale() -> Vec3 {
+        Vec3::new(1., 1., 1.)
+    }
+
+    fn no_translation() -> Vec3 {
+        Vec3::new(0., 0., 0.)
+    }
+
+    pub fn with_children(mut self, children: Vec<Node>) -> Node {
+        self.children = children;
+        self
+    }
+
+    /// Finds a descendant by the indexes of the children leading to it. An empty path is this node.
+    pub fn descendant_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&i, path)) => self.children.get_mut(i)?.descendant_mut(path),
+        }
+    }
+
+    /// The transformation from this node's space to its parent's.
+    pub fn transformation(&self) -> Mat4 {
+        self.translation.to_homogenous_translation()
+            * self.rotation.to_homogenous_rotation()
+            * self.scale.to_homogenous_scale()
+    }
+
+    /// Adds the shapes in this node and its descendants to `objects`, placed in the world given the transformation
+    /// from the parent's space to the world's.
+    pub fn collect_objects(&self, parent_transformation: &Mat4, objects: &mut Vec<Object>) {
+        let transformation = parent_transformation * &self.transformation();
+        if let Some((shape, material)) = self.shape {
+            objects.push(Object {
+                shape,
+                material,
+                transformation,
+            });
+        }
+        for child in self.children.iter() {
+            child.collect_objects(&transformation, objects);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scene::node::{Node, Shape};
+    use crate::vec3::{Mat3, Mat4, Vec3};
+
+    #[test]
+    fn test_children_follow_their_parent() {
+        let mut arm = Node::new_group(
+            Vec3::new(1., 1., 1.),
+            Mat3::identity(),
+            Vec3::new(0., 1., 0.),
+        )
+        .with_children(vec![Node::new(
+            Shape::Sphere,
+            0,
+            Vec3::new(2., 2., 2.),
+            Mat3::identity(),
+            Vec3::new(1., 0., 0.),
+        )
+        .with_children(vec![Node::new(
+            Shape::Sphere,
+            0,
+            Vec3::new(1., 1., 1.),
+            Mat3::identity(),
+            Vec3::new(1., 0., 0.),
+        )])]);
+
+        let world_position = |arm: &Node| {
+            let mut objects = Vec::new();
+            arm.collect_objects(&Mat4::identity(), &mut objects);
+            objects
+                .iter()
+                .map(|object| {
+                    (&object.transformation * Vec3::new(0., 0., 0.).to_vec4(1.)).to_vec3()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The grandchild's offset is scaled by its parent.
+        assert_eq!(
+            world_position(&arm),
+            vec![Vec3::new(1., 1., 0.), Vec3::new(3., 1., 0.)]
+        );
+
+        // Rotating the group swings both shapes around it.
+        arm.rotation = Mat3::new_oz_rotation_matrix(-90.);
+        let positions = world_position(&arm);
+        assert!((&positions[0] - &Vec3::new(0., 2., 0.)).len() < 1e-9);
+        assert!((&positions[1] - &Vec3::new(0., 4., 0.)).len() < 1e-9);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::color::decode_srgb;
+use crate::vec3::{Color, Vec3};
+use anyhow::Result;
+use image::io::Reader as ImageReader;
+use image::ImageFormat;
+use std::io::Cursor;
+
+/// An image to color surfaces with, or a normal map to shade them with. The texels of color images are decoded from
+/// sRGB to linear colors when they're loaded, so they can be lit and filtered.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    // Row by row from the top left, from 0 to 255.
+    texels: Vec<Color>,
+}
+
+impl Texture {
+    /// Loads a JPEG of colors, encoded as sRGB.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Texture> {
+        // There are only 256 encoded values of each channel, so each is decoded once.
+        let decoded: Vec<f64> = (0..=255)
+            .map(|c| decode_srgb(c as f64 / 255.) * 255.)
+            .collect();
+        Texture::decode(bytes, &decoded)
+    }
+
+    /// Loads a JPEG of values that aren't colors, such as a normal map, which are stored as they are.
+    pub fn from_linear_bytes(bytes: &[u8]) -> Result<Texture> {
+        let decoded: Vec<f64> = (0..=255).map(|c| c as f64).collect();
+        Texture::decode(bytes, &decoded)
+    }
+
+    fn decode(bytes: &[u8], decoded: &[f64]) -> Result<Texture> {
+        let img = ImageReader::with_format(Cursor::new(bytes), ImageFormat::Jpeg)
+            .decode()?
+            .into_rgb8();
+        let texels = img
+            .pixels()
+            .map(|p| {
+                let [r, g, b] = p.0.map(|c| decoded[c as usize]);
+                Color::new(r, g, b)
+            })
+            .collect();
+        Ok(Texture {
+            width: img.width(),
+            height: img.height(),
+            texels,
+        })
+    }
+
+    /// Creates a texture from the linear color at each (u, v), from 0 to 255, such as one generated procedurally.
+    pub fn from_fn(width: u32, height: u32, color: impl Fn(f64, f64) -> Color) -> Texture {
+        let mut texels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let u = x as f64 / (width - 1).max(1) as f64;
+                let v = y as f64 / (height - 1).max(1) as f64;
+                texels.push(color(u, v));
+            }
+        }
+        Texture {
+            width,
+            height,
+            texels,
+        }
+    }
+
+    /// Creates a normal map from the normal at each (u, v), where x is along u, y is up the texture and z is out of
+    /// the surface. Each normal is stored as a color, with each of its components mapped from -1 to 1 to 0 to 255.
+    pub fn from_normals(width: u32, height: u32, normal: impl Fn(f64, f64) -> Vec3) -> Texture {
+        Texture::from_fn(width, height, |u, v| {
+            let normal = normal(u, v);
+            let normal = normal / normal.len();
+            (normal + Vec3::new(1., 1., 1.)) * 127.5
+        })
+    }
+
+    /// The normal at (u, v) of a normal map, in the same space as `from_normals` takes them.
+    pub fn texel_normal(&self, u: f64, v: f64) -> Vec3 {
+        self.texel_color(u, v) / 127.5 - &Vec3::new(1., 1., 1.)
+    }
+
+    /// The height at (u, v) of a height map, from 0 for black to 1 for white.
+    pub fn texel_height(&self, u: f64, v: f64) -> f64 {
+        let color = self.texel_color(u, v);
+        (color[0] + color[1] + color[2]) / (3. * 255.)
+    }
+
+    /// The normal at (u, v) of a height map, in the same space as `texel_normal`, found from how steeply it rises
+    /// over a texel either way. White stands `depth` over black, as a fraction of the width of the texture.
+    pub fn height_normal(&self, u: f64, v: f64, depth: f64) -> Vec3 {
+        let du = 1. / (self.width - 1).max(1) as f64;
+        let dv = 1. / (self.height - 1).max(1) as f64;
+        let slope_u = (self.texel_height(u + du, v) - self.texel_height(u - du, v)) / (2. * du);
+        let slope_v = (self.texel_height(u, v + dv) - self.texel_height(u, v - dv)) / (2. * dv);
+        // Up the texture is against v.
+        Vec3::new(-slope_u * depth, slope_v * depth, 1.)
+    }
+
+    /// The color at (u, v), filtered between the nearest texels. Coordinates outside the texture take the color at its
+    /// edge.
+    pub fn texel_color(&self, u: f64, v: f64) -> Color {
+        let x = u.clamp(0., 1.) * (self.width - 1) as f64;
+        let y = v.clamp(0., 1.) * (self.height - 1) as f64;
+        let fx = x.fract();
+        let fy = y.fract();
+        let tx = x.floor() as u32;
+        let ty = y.floor() as u32;
+        let tx1 = (tx + 1).clamp(0, self.width - 1);
+        let ty1 = (ty + 1).clamp(0, self.height - 1);
+
+        let tl = self.pixel_color(tx, ty);
+        let tr = self.pixel_color(tx1, ty);
+        let bl = self.pixel_color(tx, ty1);
+        let br = self.pixel_color(tx1, ty1);
+
+        let ct = tr * fx + tl * (1. - fx);
+        let cb = br * fx + bl * (1. - fx);
+        return cb * fy + ct * (1. - fy);
+    }
+
+    fn pixel_color(&self, x: u32, y: u32) -> Color {
+        self.texels[(y * self.width + x) as usize]
+    }
+}
@@ -1,6 +1,10 @@
+use serde::Deserialize;
+use std::array::from_fn;
 use std::ops::{Add, Div, Index, Mul, Neg, Sub};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+// In scene files, vectors are written as arrays of their components.
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(from = "[f64; 3]")]
 pub struct Vec3 {
     pub e: [f64; 3],
 }
@@ -29,6 +33,19 @@ impl Vec3 {
         self.dot(self).sqrt()
     }
 
+    pub fn is_finite(&self) -> bool {
+        self.e.iter().all(|c| c.is_finite())
+    }
+
+    /// Multiplies each component by the matching one of `rhs`, such as to filter a light's color by a surface's.
+    pub fn component_mul(&self, rhs: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.e[0] * rhs.e[0],
+            self.e[1] * rhs.e[1],
+            self.e[2] * rhs.e[2],
+        )
+    }
+
     pub fn reflect(&self, normal: &Vec3) -> Vec3 {
         normal * normal.dot(self) * 2. - self
     }
@@ -177,11 +194,62 @@ impl Index<usize> for Vec3 {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+impl From<[f64; 3]> for Vec3 {
+    fn from(e: [f64; 3]) -> Vec3 {
+        Vec3 { e }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Deserialize)]
+#[serde(from = "Rotation")]
 pub struct Mat3 {
     e: [[f64; 3]; 3],
 }
 
+/// The ways a rotation can be written in a scene file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Rotation {
+    // The rows of the matrix.
+    Matrix([[f64; 3]; 3]),
+
+    // As for `Mat3::new_look_at`, with up along y unless it's given.
+    LookAt {
+        from: Vec3,
+        to: Vec3,
+        #[serde(default = "Rotation::up")]
+        up: Vec3,
+    },
+
+    // As for `Mat3::new_euler_rotation_matrix`, in degrees, with any that aren't given left at 0.
+    Euler {
+        #[serde(default)]
+        yaw: f64,
+        #[serde(default)]
+        pitch: f64,
+        #[serde(default)]
+        roll: f64,
+    },
+}
+
+impl Rotation {
+    fn up() -> Vec3 {
+        Vec3::new(0., 1., 0.)
+    }
+}
+
+impl From<Rotation> for Mat3 {
+    fn from(rotation: Rotation) -> Mat3 {
+        match rotation {
+            Rotation::Matrix(e) => Mat3::new(e),
+            Rotation::LookAt { from, to, up } => Mat3::new_look_at(&from, &to, &up),
+            Rotation::Euler { yaw, pitch, roll } => {
+                Mat3::new_euler_rotation_matrix(yaw, pitch, roll)
+            }
+        }
+    }
+}
+
 impl Mat3 {
     pub fn new(e: [[f64; 3]; 3]) -> Mat3 {
         Mat3 { e }
@@ -193,12 +261,84 @@ impl Mat3 {
         }
     }
 
+    pub fn new_ox_rotation_matrix(degrees: f64) -> Mat3 {
+        let cos = (degrees * std::f64::consts::PI / 180.0).cos();
+        let sin = (degrees * std::f64::consts::PI / 180.0).sin();
+        Mat3::new([[1., 0., 0.], [0., cos, sin], [0., -sin, cos]])
+    }
+
     pub fn new_oy_rotation_matrix(degrees: f64) -> Mat3 {
         let cos = (degrees * std::f64::consts::PI / 180.0).cos();
         let sin = (degrees * std::f64::consts::PI / 180.0).sin();
         Mat3::new([[cos, 0., -sin], [0., 1., 0.], [sin, 0., cos]])
     }
 
+    pub fn new_oz_rotation_matrix(degrees: f64) -> Mat3 {
+        let cos = (degrees * std::f64::consts::PI / 180.0).cos();
+        let sin = (degrees * std::f64::consts::PI / 180.0).sin();
+        Mat3::new([[cos, sin, 0.], [-sin, cos, 0.], [0., 0., 1.]])
+    }
+
+    /// Rotation about an arbitrary axis, in the same direction as the rotations about the x, y and z axes.
+    pub fn new_axis_rotation_matrix(axis: &Vec3, degrees: f64) -> Mat3 {
+        let cos = (degrees * std::f64::consts::PI / 180.0).cos();
+        let sin = (degrees * std::f64::consts::PI / 180.0).sin();
+        let k = axis / axis.len();
+        let t = 1. - cos;
+        Mat3::new([
+            [
+                cos + t * k[0] * k[0],
+                t * k[0] * k[1] + sin * k[2],
+                t * k[0] * k[2] - sin * k[1],
+            ],
+            [
+                t * k[1] * k[0] - sin * k[2],
+                cos + t * k[1] * k[1],
+                t * k[1] * k[2] + sin * k[0],
+            ],
+            [
+                t * k[2] * k[0] + sin * k[1],
+                t * k[2] * k[1] - sin * k[0],
+                cos + t * k[2] * k[2],
+            ],
+        ])
+    }
+
+    /// Rotation by yaw about the y axis, pitch about the x axis and roll about the z axis, applied in reverse order.
+    pub fn new_euler_rotation_matrix(yaw: f64, pitch: f64, roll: f64) -> Mat3 {
+        Mat3::new_oy_rotation_matrix(yaw)
+            * Mat3::new_ox_rotation_matrix(pitch)
+            * Mat3::new_oz_rotation_matrix(roll)
+    }
+
+    /// The orientation of something at `from` facing `to`: its z axis points towards `to`, and its y axis is as close
+    /// to `up` as it can be. Facing along `up` leaves the y axis undecided, so whichever of the x, y and z axes is the
+    /// furthest from parallel to the direction faced stands in for `up`.
+    pub fn new_look_at(from: &Vec3, to: &Vec3, up: &Vec3) -> Mat3 {
+        let forward = to - from;
+        let forward = &forward / forward.len();
+        let mut right = up.cross_product(&forward);
+        if right.len() <= 1e-9 * up.len() {
+            let axes = [
+                Vec3::new(1., 0., 0.),
+                Vec3::new(0., 1., 0.),
+                Vec3::new(0., 0., 1.),
+            ];
+            let up = axes
+                .iter()
+                .min_by(|a, b| a.dot(&forward).abs().total_cmp(&b.dot(&forward).abs()))
+                .unwrap();
+            right = up.cross_product(&forward);
+        }
+        let right = &right / right.len();
+        let up = forward.cross_product(&right);
+        Mat3::new([
+            [right[0], up[0], forward[0]],
+            [right[1], up[1], forward[1]],
+            [right[2], up[2], forward[2]],
+        ])
+    }
+
     pub fn to_homogenous_rotation(&self) -> Mat4 {
         Mat4 {
             e: [
@@ -219,6 +359,46 @@ impl Mat3 {
             ],
         }
     }
+
+    pub fn determinant(&self) -> f64 {
+        let e = &self.e;
+        e[0][0] * (e[1][1] * e[2][2] - e[1][2] * e[2][1])
+            - e[0][1] * (e[1][0] * e[2][2] - e[1][2] * e[2][0])
+            + e[0][2] * (e[1][0] * e[2][1] - e[1][1] * e[2][0])
+    }
+
+    /// An upper bound on how much the matrix can lengthen a vector. It's exact when the columns are orthogonal, as
+    /// they are for any combination of a rotation and a scale.
+    pub fn max_stretch(&self) -> f64 {
+        // The largest eigenvalue of MᵀM is the square of the largest stretch, and is bounded by its largest absolute
+        // row sum. MᵀM is diagonal when the columns are orthogonal, making the bound exact.
+        let m = self.transpose() * *self;
+        (0..3)
+            .map(|r| m.e[r].iter().map(|e| e.abs()).sum::<f64>())
+            .fold(0., f64::max)
+            .sqrt()
+    }
+
+    /// Returns the inverse, or None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat3> {
+        let det = self.determinant();
+        // Any fixed tolerance would be too loose for small scales, whose determinants are tiny, so only a determinant
+        // that can't be divided by counts as singular.
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+
+        // The transpose of the matrix of cofactors, divided by the determinant.
+        let e = &self.e;
+        let inverse = from_fn(|c| {
+            from_fn(|r| {
+                let (r1, r2) = ((r + 1) % 3, (r + 2) % 3);
+                let (c1, c2) = ((c + 1) % 3, (c + 2) % 3);
+                (e[r1][c1] * e[r2][c2] - e[r1][c2] * e[r2][c1]) / det
+            })
+        });
+        Some(Mat3 { e: inverse })
+    }
 }
 
 impl Mul<Vec3> for &Mat3 {
@@ -233,6 +413,30 @@ impl Mul<Vec3> for &Mat3 {
     }
 }
 
+impl Mul<&Mat3> for &Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, m: &Mat3) -> Self::Output {
+        let e = self.e.map(|row| {
+            from_fn(|c| {
+                row.iter()
+                    .zip(m.e.iter())
+                    .map(|(a, m_row)| a * m_row[c])
+                    .sum()
+            })
+        });
+        Mat3 { e }
+    }
+}
+
+impl Mul<Mat3> for Mat3 {
+    type Output = Mat3;
+
+    fn mul(self, m: Mat3) -> Self::Output {
+        &self * &m
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Vec4 {
     e: [f64; 4],
@@ -245,8 +449,11 @@ impl Vec4 {
         }
     }
 
-    pub fn dot(&self, rhs: &Vec3) -> f64 {
-        (self.e[0] * rhs.e[0]) + (self.e[1] * rhs.e[1]) + (self.e[2] * rhs.e[2])
+    pub fn dot(&self, rhs: &Vec4) -> f64 {
+        (self.e[0] * rhs.e[0])
+            + (self.e[1] * rhs.e[1])
+            + (self.e[2] * rhs.e[2])
+            + (self.e[3] * rhs.e[3])
     }
 
     pub fn to_vec3(&self) -> Vec3 {
@@ -289,6 +496,60 @@ impl Mat4 {
             ],
         }
     }
+
+    pub fn transpose(&self) -> Mat4 {
+        Mat4 {
+            e: from_fn(|r| self.e.map(|row| row[r])),
+        }
+    }
+
+    /// The upper-left 3x3 matrix, which holds the rotation and scale of a homogeneous transformation.
+    pub fn to_mat3(&self) -> Mat3 {
+        Mat3::new([
+            [self.e[0][0], self.e[0][1], self.e[0][2]],
+            [self.e[1][0], self.e[1][1], self.e[1][2]],
+            [self.e[2][0], self.e[2][1], self.e[2][2]],
+        ])
+    }
+
+    /// The matrix left after removing row r and column c.
+    fn minor(&self, r: usize, c: usize) -> Mat3 {
+        let mut e = [[0.; 3]; 3];
+        for (mr, row) in (0..4).filter(|&i| i != r).enumerate() {
+            for (mc, col) in (0..4).filter(|&i| i != c).enumerate() {
+                e[mr][mc] = self.e[row][col];
+            }
+        }
+        Mat3 { e }
+    }
+
+    fn cofactor(&self, r: usize, c: usize) -> f64 {
+        let sign = if (r + c).is_multiple_of(2) { 1. } else { -1. };
+        sign * self.minor(r, c).determinant()
+    }
+
+    pub fn determinant(&self) -> f64 {
+        (0..4).map(|c| self.e[0][c] * self.cofactor(0, c)).sum()
+    }
+
+    /// Returns the inverse, or None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+
+        // The transpose of the matrix of cofactors, divided by the determinant.
+        let e = from_fn(|c| from_fn(|r| self.cofactor(r, c) / det));
+        Some(Mat4 { e })
+    }
+
+    /// The matrix that transforms normals to match this transformation of vertices: the inverse transpose of its
+    /// rotation and scale. Unlike the transformation itself, this keeps normals perpendicular to their surfaces under
+    /// non-uniform scaling. Returns None if the transformation flattens everything onto a plane.
+    pub fn normal_matrix(&self) -> Option<Mat3> {
+        self.to_mat3().inverse().map(|m| m.transpose())
+    }
 }
 
 impl Mul<Vec4> for &Mat4 {
@@ -316,14 +577,14 @@ impl Mul<&Mat4> for &Mat4 {
     type Output = Mat4;
 
     fn mul(self, m: &Mat4) -> Self::Output {
-        let mut e = [[0.; 4]; 4];
-        for r in 0..4 {
-            for c in 0..4 {
-                for k in 0..4 {
-                    e[r][c] += self.e[r][k] * m.e[k][c];
-                }
-            }
-        }
+        let e = self.e.map(|row| {
+            from_fn(|c| {
+                row.iter()
+                    .zip(m.e.iter())
+                    .map(|(a, m_row)| a * m_row[c])
+                    .sum()
+            })
+        });
         Mat4 { e }
     }
 }
@@ -335,3 +596,284 @@ impl Mul<Mat4> for Mat4 {
         &self * &m
     }
 }
+
+/// A quaternion, for orientations that can be composed and smoothly interpolated. Rotations follow the same direction
+/// as `Mat3::new_axis_rotation_matrix`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Quat {
+    e: [f64; 4],
+}
+
+impl Quat {
+    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Quat {
+        Quat { e: [x, y, z, w] }
+    }
+
+    pub fn identity() -> Quat {
+        Quat::new(0., 0., 0., 1.)
+    }
+
+    pub fn from_axis_angle(axis: &Vec3, degrees: f64) -> Quat {
+        let half_angle = degrees * std::f64::consts::PI / 180.0 / 2.;
+        let v = axis * (-half_angle.sin() / axis.len());
+        Quat::new(v[0], v[1], v[2], half_angle.cos())
+    }
+
+    /// Returns the axis and angle, in degrees, of the rotation. The identity rotation has an arbitrary axis.
+    pub fn to_axis_angle(self) -> (Vec3, f64) {
+        let q = self.normalize();
+        let half_angle = q.e[3].clamp(-1., 1.).acos();
+        let sin = half_angle.sin();
+        if sin.abs() < 1e-12 {
+            (Vec3::new(1., 0., 0.), 0.)
+        } else {
+            let axis = Vec3::new(q.e[0], q.e[1], q.e[2]) / -sin;
+            (axis, 2. * half_angle * 180.0 / std::f64::consts::PI)
+        }
+    }
+
+    pub fn dot(&self, rhs: &Quat) -> f64 {
+        (0..4).map(|i| self.e[i] * rhs.e[i]).sum()
+    }
+
+    pub fn len(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Quat {
+        let len = self.len();
+        Quat {
+            e: self.e.map(|e| e / len),
+        }
+    }
+
+    /// The opposite rotation, for a unit quaternion.
+    pub fn conjugate(&self) -> Quat {
+        Quat::new(-self.e[0], -self.e[1], -self.e[2], self.e[3])
+    }
+
+    /// Normalized linear interpolation: cheaper than `slerp`, but doesn't rotate at a constant speed.
+    pub fn nlerp(&self, rhs: &Quat, t: f64) -> Quat {
+        let rhs = self.shortest_path_to(rhs);
+        let mut e = [0.; 4];
+        for (e, (a, b)) in e.iter_mut().zip(self.e.iter().zip(rhs.e.iter())) {
+            *e = a * (1. - t) + b * t;
+        }
+        Quat { e }.normalize()
+    }
+
+    /// Spherical linear interpolation, which rotates from `self` to `rhs` at a constant speed as `t` goes from 0 to 1.
+    pub fn slerp(&self, rhs: &Quat, t: f64) -> Quat {
+        let rhs = self.shortest_path_to(rhs);
+        let cos = self.dot(&rhs).clamp(-1., 1.);
+        if cos > 0.9995 {
+            // The quaternions are so close that the sine below would be unstable.
+            return self.nlerp(&rhs, t);
+        }
+
+        let angle = cos.acos();
+        let a = ((1. - t) * angle).sin() / angle.sin();
+        let b = (t * angle).sin() / angle.sin();
+        let mut e = [0.; 4];
+        for (e, (p, q)) in e.iter_mut().zip(self.e.iter().zip(rhs.e.iter())) {
+            *e = p * a + q * b;
+        }
+        Quat { e }
+    }
+
+    /// q and -q are the same rotation, but interpolating between them goes the long way round.
+    fn shortest_path_to(&self, rhs: &Quat) -> Quat {
+        if self.dot(rhs) < 0. {
+            Quat {
+                e: rhs.e.map(|e| -e),
+            }
+        } else {
+            *rhs
+        }
+    }
+
+    pub fn to_mat3(self) -> Mat3 {
+        let [x, y, z, w] = self.normalize().e;
+        Mat3::new([
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - z * w),
+                2. * (x * z + y * w),
+            ],
+            [
+                2. * (x * y + z * w),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - x * w),
+            ],
+            [
+                2. * (x * z - y * w),
+                2. * (y * z + x * w),
+                1. - 2. * (x * x + y * y),
+            ],
+        ])
+    }
+
+    pub fn to_homogenous_rotation(self) -> Mat4 {
+        self.to_mat3().to_homogenous_rotation()
+    }
+}
+
+impl From<Quat> for Mat3 {
+    fn from(q: Quat) -> Mat3 {
+        q.to_mat3()
+    }
+}
+
+/// Composes two rotations: `a * b` rotates by `b` and then by `a`.
+impl Mul<&Quat> for &Quat {
+    type Output = Quat;
+
+    fn mul(self, q: &Quat) -> Self::Output {
+        let [x1, y1, z1, w1] = self.e;
+        let [x2, y2, z2, w2] = q.e;
+        Quat::new(
+            w1 * x2 + x1 * w2 + y1 * z2 - z1 * y2,
+            w1 * y2 - x1 * z2 + y1 * w2 + z1 * x2,
+            w1 * z2 + x1 * y2 - y1 * x2 + z1 * w2,
+            w1 * w2 - x1 * x2 - y1 * y2 - z1 * z2,
+        )
+    }
+}
+
+impl Mul<Quat> for Quat {
+    type Output = Quat;
+
+    fn mul(self, q: Quat) -> Self::Output {
+        &self * &q
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vec3::{Mat3, Mat4, Quat, Vec3};
+
+    fn assert_mat3_eq(a: &Mat3, b: &Mat3) {
+        for r in 0..3 {
+            for c in 0..3 {
+                assert!((a.e[r][c] - b.e[r][c]).abs() < 1e-9, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_axis_rotation() {
+        let degrees = 37.;
+        assert_mat3_eq(
+            &Mat3::new_axis_rotation_matrix(&Vec3::new(2., 0., 0.), degrees),
+            &Mat3::new_ox_rotation_matrix(degrees),
+        );
+        assert_mat3_eq(
+            &Mat3::new_axis_rotation_matrix(&Vec3::new(0., 1., 0.), degrees),
+            &Mat3::new_oy_rotation_matrix(degrees),
+        );
+        assert_mat3_eq(
+            &Mat3::new_axis_rotation_matrix(&Vec3::new(0., 0., 3.), degrees),
+            &Mat3::new_oz_rotation_matrix(degrees),
+        );
+    }
+
+    #[test]
+    fn test_look_at() {
+        let look_at = Mat3::new_look_at(
+            &Vec3::new(3., 0., 1.),
+            &Vec3::new(0., 0., 4.),
+            &Vec3::new(0., 1., 0.),
+        );
+        assert_mat3_eq(&look_at, &Mat3::new_oy_rotation_matrix(45.));
+        assert_mat3_eq(&look_at, &Mat3::new_euler_rotation_matrix(45., 0., 0.));
+
+        // Looking straight up or down still gives a rotation facing the right way.
+        for to in [Vec3::new(0., 5., 0.), Vec3::new(0., -5., 0.)] {
+            let look_at = Mat3::new_look_at(&Vec3::new(0., 0., 0.), &to, &Vec3::new(0., 1., 0.));
+            assert_mat3_eq(&(look_at.transpose() * look_at), &Mat3::identity());
+            assert!((look_at.determinant() - 1.).abs() < 1e-9);
+            assert!((&(&look_at * Vec3::new(0., 0., 1.)) - &(&to / 5.)).len() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_quat() {
+        let axis = Vec3::new(1., 2., -0.5);
+        let q = Quat::from_axis_angle(&axis, 70.);
+        assert_mat3_eq(&q.to_mat3(), &Mat3::new_axis_rotation_matrix(&axis, 70.));
+
+        let (q_axis, degrees) = q.to_axis_angle();
+        assert!((degrees - 70.).abs() < 1e-9);
+        assert!((&q_axis - &(&axis / axis.len())).len() < 1e-9);
+
+        let r = Quat::from_axis_angle(&Vec3::new(0., 1., 0.), -25.);
+        assert_mat3_eq(&(q * r).to_mat3(), &(q.to_mat3() * r.to_mat3()));
+    }
+
+    #[test]
+    fn test_slerp() {
+        let axis = Vec3::new(0., 1., 0.);
+        let from = Quat::from_axis_angle(&axis, 10.);
+        let to = Quat::from_axis_angle(&axis, 130.);
+        for t in [0., 0.25, 0.5, 1.] {
+            let expected = Mat3::new_oy_rotation_matrix(10. + 120. * t);
+            assert_mat3_eq(&from.slerp(&to, t).to_mat3(), &expected);
+        }
+        assert_mat3_eq(
+            &from.nlerp(&to, 0.5).to_mat3(),
+            &Mat3::new_oy_rotation_matrix(70.),
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        let m = Mat3::new([[2., 0., 1.], [1., 3., 0.], [0., -1., 4.]]);
+        assert!((m.determinant() - 23.).abs() < 1e-9);
+        assert_mat3_eq(&(m * m.inverse().unwrap()), &Mat3::identity());
+        assert_eq!(
+            Mat3::new([[1., 2., 3.], [2., 4., 6.], [0., 0., 1.]]).inverse(),
+            None
+        );
+
+        let t = Vec3::new(1., -2., 3.).to_homogenous_translation()
+            * Mat3::new_euler_rotation_matrix(30., 20., 10.).to_homogenous_rotation()
+            * Vec3::new(2., 3., 4.).to_homogenous_scale();
+        assert!((t.determinant() - 24.).abs() < 1e-9);
+        let identity = t * t.inverse().unwrap();
+        assert_mat3_eq(&identity.to_mat3(), &Mat3::identity());
+        assert_eq!(t.transpose().transpose(), t);
+        assert!(Mat4::new_homogeneous_scaling_matrix(0.).inverse().is_none());
+
+        // Small scales are invertible, even though their determinants are much smaller than the scales themselves.
+        let small = Vec3::new(1e-6, 1e-6, 1e-6).to_homogenous_scale();
+        assert_mat3_eq(
+            &(small * small.inverse().unwrap()).to_mat3(),
+            &Mat3::identity(),
+        );
+        let small = small.to_mat3();
+        assert_mat3_eq(&(small * small.inverse().unwrap()), &Mat3::identity());
+    }
+
+    #[test]
+    fn test_normal_matrix() {
+        // A plane at 45 degrees, stretched along x, stays perpendicular to its transformed normal.
+        let scale = Vec3::new(4., 1., 1.).to_homogenous_scale();
+        let tangent = Vec3::new(1., 1., 0.);
+        let normal = Vec3::new(1., -1., 0.);
+        let tangent = (&scale * tangent.to_vec4(0.)).to_vec3();
+        let normal = &scale.normal_matrix().unwrap() * normal;
+        assert!(tangent.dot(&normal).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_max_stretch() {
+        let m = Mat3::new_euler_rotation_matrix(30., 20., 10.)
+            * Vec3::new(2., -3., 0.5).to_homogenous_scale().to_mat3();
+        assert!((m.max_stretch() - 3.).abs() < 1e-9);
+
+        // A shear stretches (1, 1, 0) by more than any of the columns, which the bound still covers.
+        let shear = Mat3::new([[1., 1., 0.], [0., 1., 0.], [0., 0., 1.]]);
+        let v = Vec3::new(1., 1., 0.);
+        assert!(shear.max_stretch() >= (&shear * v).len() / v.len());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::animation::Timeline;
+use crate::color::ToneMapping;
+use crate::controls::CameraControls;
+use crate::renderer::{Backend, Framebuffer, Renderer, Statistics};
+use crate::scene::camera::Camera;
+use crate::scene::examples::Example;
+use crate::scene::Scene;
+use crate::utils;
+use crate::vec3::{Mat3, Vec3};
+use wasm_bindgen::prelude::*;
+
+/// Renders one of the example scenes with one of the renderers, keeping the scene and pixels between frames so only
+/// what changes has to be updated.
+#[wasm_bindgen]
+pub struct Viewer {
+    renderer: Box<dyn Renderer>,
+    framebuffer: Framebuffer,
+    scene: Scene,
+    timeline: Timeline,
+    controls: CameraControls,
+    tone_mapping: ToneMapping,
+    // What the rendered colors are multiplied by before they're tone mapped.
+    exposure: f64,
+}
+
+#[wasm_bindgen]
+impl Viewer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        backend: Backend,
+        example: Example,
+        canvas_height: usize,
+        canvas_width: usize,
+    ) -> Viewer {
+        let mut viewer = Viewer::from_scene(backend, example.scene(), canvas_height, canvas_width);
+        viewer.timeline = example.timeline();
+        viewer.controls = example.controls();
+        viewer
+    }
+
+    /// Tone maps the last render again with another operator, and every render after it.
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+        self.framebuffer.tone_map(self.tone_mapping, self.exposure);
+    }
+
+    /// Tone maps the last render again at another exposure, and every render after it.
+    pub fn set_exposure(&mut self, exposure: f64) {
+        self.exposure = exposure;
+        self.framebuffer.tone_map(self.tone_mapping, self.exposure);
+    }
+
+    /// Renders with another renderer from now on, keeping the scene as it is.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.renderer = backend.renderer();
+    }
+
+    /// Replaces the scene with one of the examples, as it is at the start of its animation.
+    pub fn set_example(&mut self, example: Example) {
+        self.scene = example.scene();
+        self.timeline = example.timeline();
+        self.controls = example.controls();
+    }
+
+    /// Moves the camera to (x, y, z), turned by the yaw and pitch, in degrees.
+    pub fn set_camera(&mut self, x: f64, y: f64, z: f64, yaw: f64, pitch: f64) {
+        let distance = self.controls.distance;
+        self.controls = CameraControls::new(Vec3::new(x, y, z), yaw, pitch, distance);
+        self.update_camera();
+    }
+
+    /// Turns the camera around the point it's looking at, in degrees.
+    pub fn orbit(&mut self, yaw: f64, pitch: f64) {
+        self.controls.orbit(yaw, pitch);
+        self.update_camera();
+    }
+
+    /// Turns the camera where it stands, in degrees.
+    pub fn look(&mut self, yaw: f64, pitch: f64) {
+        self.controls.look(yaw, pitch);
+        self.update_camera();
+    }
+
+    /// Moves the camera towards the point it's looking at, closing the distance by `factor`.
+    pub fn zoom(&mut self, factor: f64) {
+        self.controls.zoom(factor);
+        self.update_camera();
+    }
+
+    /// Moves the camera along its own axes.
+    pub fn fly(&mut self, right: f64, up: f64, forward: f64) {
+        self.controls.fly(right, up, forward);
+        self.update_camera();
+    }
+
+    fn update_camera(&mut self) {
+        self.scene.camera.position = self.controls.position();
+        self.scene.camera.orientation = self.controls.orientation();
+    }
+
+    /// Moves one of the top-level nodes of the scene, such as one of the spheres. Unknown nodes are ignored.
+    pub fn set_node_translation(&mut self, node: usize, x: f64, y: f64, z: f64) {
+        if let Some(node) = self.scene.nodes.get_mut(node) {
+            node.translation = Vec3::new(x, y, z);
+        }
+    }
+
+    /// Turns one of the top-level nodes of the scene by the yaw, pitch and roll, in degrees. Unknown nodes are ignored.
+    pub fn set_node_rotation(&mut self, node: usize, yaw: f64, pitch: f64, roll: f64) {
+        if let Some(node) = self.scene.nodes.get_mut(node) {
+            node.rotation = Mat3::new_euler_rotation_matrix(yaw, pitch, roll);
+        }
+    }
+
+    /// Scales one of the top-level nodes of the scene. Unknown nodes are ignored.
+    pub fn set_node_scale(&mut self, node: usize, x: f64, y: f64, z: f64) {
+        if let Some(node) = self.scene.nodes.get_mut(node) {
+            node.scale = Vec3::new(x, y, z);
+        }
+    }
+
+    /// Poses the scene as it is `time` seconds into its animation.
+    pub fn set_time(&mut self, time: f64) {
+        self.scene.animate(&self.timeline, time);
+    }
+
+    /// Renders the scene as it's currently posed into the pixels.
+    pub fn render(&mut self) -> Statistics {
+        let statistics = self.renderer.render(&self.scene, &mut self.framebuffer);
+        self.framebuffer.tone_map(self.tone_mapping, self.exposure);
+        statistics
+    }
+
+    /// Renders a preview of the scene with about one sample for each `block_size` by `block_size` block of pixels.
+    pub fn render_preview(&mut self, block_size: usize) -> Statistics {
+        let statistics =
+            self.renderer
+                .render_preview(&self.scene, &mut self.framebuffer, block_size);
+        self.framebuffer.tone_map(self.tone_mapping, self.exposure);
+        statistics
+    }
+
+    /// Whether the image should be refined over several frames with `render_preview`, rather than rendered in full.
+    pub fn is_progressive(&self) -> bool {
+        self.renderer.is_progressive()
+    }
+
+    /// Where the RGBA pixels are in wasm memory. They stay there for the life of the viewer, so JS can view them
+    /// without copying.
+    pub fn pixels_ptr(&self) -> *const u8 {
+        self.framebuffer.pixels.as_ptr()
+    }
+
+    /// The number of bytes of pixels at `pixels_ptr`.
+    pub fn pixels_len(&self) -> usize {
+        self.framebuffer.pixels.len()
+    }
+}
+
+impl Viewer {
+    /// Creates a viewer of a scene other than the examples, such as one loaded from a file, which isn't animated.
+    pub fn from_scene(
+        backend: Backend,
+        scene: Scene,
+        canvas_height: usize,
+        canvas_width: usize,
+    ) -> Viewer {
+        utils::set_panic_hook();
+        let Camera {
+            position,
+            orientation,
+            ..
+        } = scene.camera;
+        Viewer {
+            renderer: backend.renderer(),
+            framebuffer: Framebuffer::new(canvas_height, canvas_width),
+            scene,
+            timeline: Timeline::new(Vec::new()),
+            controls: CameraControls::from_orientation(position, &orientation, 1.),
+            tone_mapping: ToneMapping::default(),
+            exposure: 1.,
+        }
+    }
+
+    /// The RGBA pixels of the last render.
+    pub fn pixels(&self) -> &[u8] {
+        &self.framebuffer.pixels
+    }
+
+    /// The linear RGBA colors of the last render, before they were tone mapped.
+    pub fn colors(&self) -> &[f32] {
+        &self.framebuffer.colors
+    }
+}
+
+/// Renders one of the example scenes as it is `time` seconds into its animation.
+#[wasm_bindgen]
+pub fn render_example(
+    backend: Backend,
+    example: Example,
+    canvas_height: usize,
+    canvas_width: usize,
+    time: f64,
+) -> Vec<u8> {
+    let mut viewer = Viewer::new(backend, example, canvas_height, canvas_width);
+    viewer.set_time(time);
+    viewer.render();
+    viewer.framebuffer.pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::ToneMapping;
+    use crate::renderer::Backend;
+    use crate::scene::examples::Example;
+    use crate::vec3::Vec3;
+    use crate::viewer::{render_example, Viewer};
+
+    #[test]
+    fn test_render_example() {
+        let _res = render_example(Backend::Rasterizer, Example::Crates, 600, 600, 0.);
+    }
+
+    #[test]
+    fn test_render_example_frame() {
+        let first = render_example(Backend::Rasterizer, Example::Crates, 60, 60, 0.);
+        assert_eq!(
+            render_example(Backend::Rasterizer, Example::Crates, 60, 60, 0.),
+            first
+        );
+        assert_ne!(
+            render_example(Backend::Rasterizer, Example::Crates, 60, 60, 1.),
+            first
+        );
+    }
+
+    #[test]
+    fn test_controls_start_at_the_scenes_camera() {
+        let camera = Example::Spheres.scene().camera;
+        let mut viewer = Viewer::new(Backend::Raytracer, Example::Spheres, 20, 20);
+        viewer.orbit(0., 0.);
+        assert!((&viewer.scene.camera.position - &camera.position).len() < 1e-9);
+        for axis in [
+            Vec3::new(1., 0., 0.),
+            Vec3::new(0., 1., 0.),
+            Vec3::new(0., 0., 1.),
+        ] {
+            let expected = &camera.orientation * axis;
+            assert!((&(&viewer.scene.camera.orientation * axis) - &expected).len() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_tone_mapping() {
+        let mut viewer = Viewer::new(Backend::Raytracer, Example::Spheres, 30, 40);
+        viewer.render();
+        let colors = viewer.colors().to_vec();
+        let aces = viewer.pixels().to_vec();
+
+        // The last render is mapped again, without rendering it again.
+        viewer.set_tone_mapping(ToneMapping::Clamp);
+        assert_ne!(viewer.pixels(), aces);
+        viewer.set_exposure(2.);
+        let brighter = viewer.pixels().to_vec();
+        viewer.set_exposure(1.);
+        assert!(brighter.iter().zip(viewer.pixels()).all(|(b, p)| b >= p));
+        viewer.set_tone_mapping(ToneMapping::Aces);
+        assert_eq!(viewer.pixels(), aces);
+        assert_eq!(viewer.colors(), colors);
+    }
+
+    #[test]
+    fn test_rerender() {
+        let mut viewer = Viewer::new(Backend::Rasterizer, Example::Crates, 60, 60);
+        viewer.render();
+        let first = viewer.pixels().to_vec();
+        let ptr = viewer.pixels_ptr();
+
+        viewer.set_node_translation(0, -1.5, 0., 9.);
+        viewer.render();
+        assert_ne!(viewer.pixels(), first);
+
+        viewer.set_node_translation(0, -1.5, 0., 7.);
+        viewer.render();
+        assert_eq!(viewer.pixels(), first);
+
+        // Either renderer renders into the same pixels, in place.
+        viewer.set_backend(Backend::Raytracer);
+        viewer.render();
+        assert_ne!(viewer.pixels(), first);
+        assert_eq!(viewer.pixels_ptr(), ptr);
+        assert_eq!(viewer.pixels_len(), 60 * 60 * 4);
+    }
+}
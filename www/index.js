@@ -6,32 +6,174 @@ var raytracer_tab = document.getElementById("raytracer-tab");
 var rasterizer_tab = document.getElementById("rasterizer-tab");
 var raytracer = document.getElementById("raytracer");
 var rasterizer = document.getElementById("rasterizer");
+var raytracer_canvas = document.getElementById("raytracer-canvas");
+var rasterizer_canvas = document.getElementById("rasterizer-canvas");
+var scene_select = document.getElementById("scene");
+var camera_mode = document.getElementById("camera-mode");
+var tone_mapping_select = document.getElementById("tone-mapping");
+var exposure_input = document.getElementById("exposure");
+var statistics = document.getElementById("statistics");
+// The viewers keep their scenes between renders, so they're only built once. They take the canvas's height before its
+// width.
+var raytracer_viewer = new wasm.Viewer(wasm.Backend.Raytracer, wasm.Example.Spheres, raytracer_canvas.height, raytracer_canvas.width);
+var rasterizer_viewer = new wasm.Viewer(wasm.Backend.Rasterizer, wasm.Example.Crates, rasterizer_canvas.height, rasterizer_canvas.width);
+var draw_raytracer = drawer(raytracer_canvas, raytracer_viewer);
+var draw_rasterizer = drawer(rasterizer_canvas, rasterizer_viewer);
+// Degrees turned per pixel dragged, units moved per second and zoom per pixel scrolled.
+var TURN_SPEED = 0.3;
+var FLY_SPEED = 3;
+var ZOOM_SPEED = 1.001;
+// While the camera moves a progressive renderer, like the raytracer, renders one sample per block of this many pixels
+// square. Once it stops the blocks are halved every frame until every pixel has been rendered.
+var COARSEST_BLOCK_SIZE = 16;
+var active_tab = "rasterizer";
+// Either renderer can show any of the example scenes, so they can be compared. Each starts with its own.
+var EXAMPLES = {
+    crates: wasm.Example.Crates,
+    spheres: wasm.Example.Spheres,
+    materials: wasm.Example.Materials,
+    cobblestones: wasm.Example.Cobblestones
+};
+var examples = { raytracer: "spheres", rasterizer: "crates" };
+var TONE_MAPPINGS = {
+    clamp: wasm.ToneMapping.Clamp,
+    reinhard: wasm.ToneMapping.Reinhard,
+    aces: wasm.ToneMapping.Aces,
+    exposure: wasm.ToneMapping.Exposure
+};
+// The size of the blocks to render in the next frame, or 0 if the canvas is up to date.
+var block_size = 0;
+var dragging = false;
+var keys_down = {};
+var last_frame_time = performance.now();
 raytracer_tab.onclick = function () { return openTab("raytracer"); };
 rasterizer_tab.onclick = function () { return openTab("rasterizer"); };
 function openTab(tabName) {
+    active_tab = tabName;
     switch (tabName) {
         case "raytracer":
             rasterizer.style.display = "none";
             rasterizer_tab.className = "tablinks";
             raytracer.style.display = "block";
             raytracer_tab.className = "tablinks active";
-            render("raytracer-canvas", wasm.raytracer);
             break;
         case "rasterizer":
             raytracer.style.display = "none";
             raytracer_tab.className = "tablinks";
             rasterizer.style.display = "block";
             rasterizer_tab.className = "tablinks active";
-            render("rasterizer-canvas", wasm.rasterizer);
     }
+    scene_select.value = examples[tabName];
+    cameraMoved();
 }
 exports.openTab = openTab;
-function render(canvas_id, func) {
-    var canvas = document.getElementById(canvas_id);
+scene_select.onchange = function () {
+    examples[active_tab] = scene_select.value;
+    activeViewer().set_example(EXAMPLES[scene_select.value]);
+    cameraMoved();
+};
+// Both viewers show their renders the same way. The last render is mapped again, so it's only redrawn rather than
+// rendered again. The exposure slider is in stops, each doubling the light.
+tone_mapping_select.onchange = function () {
+    for (var _i = 0, _a = [raytracer_viewer, rasterizer_viewer]; _i < _a.length; _i++) {
+        var viewer = _a[_i];
+        viewer.set_tone_mapping(TONE_MAPPINGS[tone_mapping_select.value]);
+    }
+    draw();
+};
+exposure_input.oninput = function () {
+    for (var _i = 0, _a = [raytracer_viewer, rasterizer_viewer]; _i < _a.length; _i++) {
+        var viewer = _a[_i];
+        viewer.set_exposure(Math.pow(2, Number(exposure_input.value)));
+    }
+    draw();
+};
+function activeViewer() {
+    return active_tab == "raytracer" ? raytracer_viewer : rasterizer_viewer;
+}
+function cameraMoved() {
+    block_size = activeViewer().is_progressive() ? COARSEST_BLOCK_SIZE : 1;
+}
+function turn(yaw, pitch) {
+    if (camera_mode.value == "orbit") {
+        activeViewer().orbit(yaw, pitch);
+    }
+    else {
+        activeViewer().look(yaw, pitch);
+    }
+    cameraMoved();
+}
+for (var _i = 0, _a = [raytracer_canvas, rasterizer_canvas]; _i < _a.length; _i++) {
+    var canvas = _a[_i];
+    canvas.onmousedown = function () { return dragging = true; };
+    canvas.onwheel = function (event) {
+        event.preventDefault();
+        activeViewer().zoom(Math.pow(ZOOM_SPEED, -event.deltaY));
+        cameraMoved();
+    };
+}
+window.onmouseup = function () { return dragging = false; };
+window.onmousemove = function (event) {
+    if (dragging) {
+        // Dragging right or down turns the scene with the mouse.
+        turn(-event.movementX * TURN_SPEED, -event.movementY * TURN_SPEED);
+    }
+};
+window.onkeydown = function (event) { return keys_down[event.key.toLowerCase()] = true; };
+window.onkeyup = function (event) { return keys_down[event.key.toLowerCase()] = false; };
+window.onblur = function () {
+    for (var key in keys_down) {
+        keys_down[key] = false;
+    }
+};
+function fly(seconds) {
+    var axis = function (positive, negative) {
+        return (keys_down[positive] ? 1 : 0) - (keys_down[negative] ? 1 : 0);
+    };
+    var right = axis("d", "a");
+    var up = axis("e", "q");
+    var forward = axis("w", "s");
+    if (right != 0 || up != 0 || forward != 0) {
+        var distance = FLY_SPEED * seconds;
+        activeViewer().fly(right * distance, up * distance, forward * distance);
+        cameraMoved();
+    }
+}
+function frame(time) {
+    fly((time - last_frame_time) / 1000);
+    last_frame_time = time;
+    if (block_size > 0) {
+        var stats = activeViewer().render_preview(block_size);
+        statistics.textContent = stats.objects + " objects, " + stats.triangles + " triangles, " + stats.rays + " rays";
+        stats.free();
+        draw();
+        block_size = Math.floor(block_size / 2);
+    }
+    requestAnimationFrame(frame);
+}
+function draw() {
+    if (active_tab == "raytracer") {
+        draw_raytracer();
+    }
+    else {
+        draw_rasterizer();
+    }
+}
+// Returns a function that draws the viewer's pixels onto the canvas. The pixels are viewed where they are in wasm
+// memory rather than copied out of it. Growing the memory replaces its buffer, so then the view has to be rebuilt.
+function drawer(canvas, viewer) {
     var ctx = canvas.getContext("2d");
-    var bytes = func(canvas.height, canvas.width);
-    var img_bytes = new Uint8ClampedArray(bytes);
-    var imageData = new ImageData(img_bytes, canvas.height, canvas.width);
-    ctx.putImageData(imageData, 0, 0);
+    var buffer = null;
+    var imageData = null;
+    return function () {
+        var memory = wasm.memory();
+        if (memory.buffer !== buffer) {
+            buffer = memory.buffer;
+            var pixels = new Uint8ClampedArray(buffer, viewer.pixels_ptr(), viewer.pixels_len());
+            imageData = new ImageData(pixels, canvas.width, canvas.height);
+        }
+        ctx.putImageData(imageData, 0, 0);
+    };
 }
 openTab("rasterizer");
+requestAnimationFrame(frame);
@@ -6,36 +6,191 @@ const rasterizer_tab = document.getElementById("rasterizer-tab")
 const raytracer = document.getElementById("raytracer")
 const rasterizer = document.getElementById("rasterizer")
 
+const raytracer_canvas = document.getElementById("raytracer-canvas") as HTMLCanvasElement
+const rasterizer_canvas = document.getElementById("rasterizer-canvas") as HTMLCanvasElement
+const scene_select = document.getElementById("scene") as HTMLSelectElement
+const camera_mode = document.getElementById("camera-mode") as HTMLSelectElement
+const tone_mapping_select = document.getElementById("tone-mapping") as HTMLSelectElement
+const exposure_input = document.getElementById("exposure") as HTMLInputElement
+const statistics = document.getElementById("statistics")
+
+// The viewers keep their scenes between renders, so they're only built once. They take the canvas's height before its
+// width.
+const raytracer_viewer = new wasm.Viewer(wasm.Backend.Raytracer, wasm.Example.Spheres, raytracer_canvas.height,
+    raytracer_canvas.width)
+const rasterizer_viewer = new wasm.Viewer(wasm.Backend.Rasterizer, wasm.Example.Crates, rasterizer_canvas.height,
+    rasterizer_canvas.width)
+const draw_raytracer = drawer(raytracer_canvas, raytracer_viewer)
+const draw_rasterizer = drawer(rasterizer_canvas, rasterizer_viewer)
+
+// Degrees turned per pixel dragged, units moved per second and zoom per pixel scrolled.
+const TURN_SPEED = 0.3
+const FLY_SPEED = 3
+const ZOOM_SPEED = 1.001
+
+// While the camera moves a progressive renderer, like the raytracer, renders one sample per block of this many pixels
+// square. Once it stops the blocks are halved every frame until every pixel has been rendered.
+const COARSEST_BLOCK_SIZE = 16
+
+let active_tab: "raytracer" | "rasterizer" = "rasterizer"
+
+// Either renderer can show any of the example scenes, so they can be compared. Each starts with its own.
+const EXAMPLES: { [name: string]: wasm.Example } = {
+    crates: wasm.Example.Crates,
+    spheres: wasm.Example.Spheres,
+    materials: wasm.Example.Materials,
+    cobblestones: wasm.Example.Cobblestones,
+}
+const examples = { raytracer: "spheres", rasterizer: "crates" }
+
+const TONE_MAPPINGS: { [name: string]: wasm.ToneMapping } = {
+    clamp: wasm.ToneMapping.Clamp,
+    reinhard: wasm.ToneMapping.Reinhard,
+    aces: wasm.ToneMapping.Aces,
+    exposure: wasm.ToneMapping.Exposure,
+}
+
+// The size of the blocks to render in the next frame, or 0 if the canvas is up to date.
+let block_size = 0
+
+let dragging = false
+const keys_down: { [key: string]: boolean } = {}
+let last_frame_time = performance.now()
+
 raytracer_tab.onclick = () => openTab("raytracer")
 rasterizer_tab.onclick = () => openTab("rasterizer")
 
 export function openTab(tabName: "raytracer" | "rasterizer") {
+    active_tab = tabName
     switch (tabName) {
         case "raytracer":
             rasterizer.style.display = "none";
             rasterizer_tab.className = "tablinks";
             raytracer.style.display = "block";
             raytracer_tab.className = "tablinks active";
-
-            render("raytracer-canvas", wasm.raytracer)
             break;
         case "rasterizer":
             raytracer.style.display = "none";
             raytracer_tab.className = "tablinks";
             rasterizer.style.display = "block";
             rasterizer_tab.className = "tablinks active";
+    }
+    scene_select.value = examples[tabName]
+    cameraMoved()
+}
 
-            render("rasterizer-canvas", wasm.rasterizer)
+scene_select.onchange = () => {
+    examples[active_tab] = scene_select.value
+    activeViewer().set_example(EXAMPLES[scene_select.value])
+    cameraMoved()
+}
+
+// Both viewers show their renders the same way. The last render is mapped again, so it's only redrawn rather than
+// rendered again. The exposure slider is in stops, each doubling the light.
+tone_mapping_select.onchange = () => {
+    for (const viewer of [raytracer_viewer, rasterizer_viewer]) {
+        viewer.set_tone_mapping(TONE_MAPPINGS[tone_mapping_select.value])
     }
+    draw()
+}
+exposure_input.oninput = () => {
+    for (const viewer of [raytracer_viewer, rasterizer_viewer]) {
+        viewer.set_exposure(Math.pow(2, Number(exposure_input.value)))
+    }
+    draw()
+}
+
+function activeViewer(): wasm.Viewer {
+    return active_tab == "raytracer" ? raytracer_viewer : rasterizer_viewer
+}
+
+function cameraMoved() {
+    block_size = activeViewer().is_progressive() ? COARSEST_BLOCK_SIZE : 1
 }
 
-function render(canvas_id: string, func: (width: number, height: number) => Uint8Array) {
-    const canvas = document.getElementById(canvas_id)  as HTMLCanvasElement
+function turn(yaw: number, pitch: number) {
+    if (camera_mode.value == "orbit") {
+        activeViewer().orbit(yaw, pitch)
+    } else {
+        activeViewer().look(yaw, pitch)
+    }
+    cameraMoved()
+}
+
+for (const canvas of [raytracer_canvas, rasterizer_canvas]) {
+    canvas.onmousedown = () => dragging = true
+    canvas.onwheel = (event: WheelEvent) => {
+        event.preventDefault()
+        activeViewer().zoom(Math.pow(ZOOM_SPEED, -event.deltaY))
+        cameraMoved()
+    }
+}
+window.onmouseup = () => dragging = false
+window.onmousemove = (event: MouseEvent) => {
+    if (dragging) {
+        // Dragging right or down turns the scene with the mouse.
+        turn(-event.movementX * TURN_SPEED, -event.movementY * TURN_SPEED)
+    }
+}
+window.onkeydown = (event: KeyboardEvent) => keys_down[event.key.toLowerCase()] = true
+window.onkeyup = (event: KeyboardEvent) => keys_down[event.key.toLowerCase()] = false
+window.onblur = () => {
+    for (const key in keys_down) {
+        keys_down[key] = false
+    }
+}
+
+function fly(seconds: number) {
+    const axis = (positive: string, negative: string) =>
+        (keys_down[positive] ? 1 : 0) - (keys_down[negative] ? 1 : 0)
+    const right = axis("d", "a")
+    const up = axis("e", "q")
+    const forward = axis("w", "s")
+    if (right != 0 || up != 0 || forward != 0) {
+        const distance = FLY_SPEED * seconds
+        activeViewer().fly(right * distance, up * distance, forward * distance)
+        cameraMoved()
+    }
+}
+
+function frame(time: number) {
+    fly((time - last_frame_time) / 1000)
+    last_frame_time = time
+
+    if (block_size > 0) {
+        const stats = activeViewer().render_preview(block_size)
+        statistics.textContent = `${stats.objects} objects, ${stats.triangles} triangles, ${stats.rays} rays`
+        stats.free()
+        draw()
+        block_size = Math.floor(block_size / 2)
+    }
+    requestAnimationFrame(frame)
+}
+
+function draw() {
+    if (active_tab == "raytracer") {
+        draw_raytracer()
+    } else {
+        draw_rasterizer()
+    }
+}
+
+// Returns a function that draws the viewer's pixels onto the canvas. The pixels are viewed where they are in wasm
+// memory rather than copied out of it. Growing the memory replaces its buffer, so then the view has to be rebuilt.
+function drawer(canvas: HTMLCanvasElement, viewer: { pixels_ptr(): number, pixels_len(): number }) {
     const ctx = canvas.getContext("2d");
-    const bytes = func(canvas.height, canvas.width);
-    const img_bytes = new Uint8ClampedArray(bytes);
-    const imageData = new ImageData(img_bytes, canvas.height, canvas.width)
-    ctx.putImageData(imageData, 0, 0)
+    let buffer: ArrayBuffer = null
+    let imageData: ImageData = null
+    return () => {
+        const memory = wasm.memory() as WebAssembly.Memory
+        if (memory.buffer !== buffer) {
+            buffer = memory.buffer
+            const pixels = new Uint8ClampedArray(buffer, viewer.pixels_ptr(), viewer.pixels_len())
+            imageData = new ImageData(pixels, canvas.width, canvas.height)
+        }
+        ctx.putImageData(imageData, 0, 0)
+    }
 }
 
 openTab("rasterizer")
+requestAnimationFrame(frame)